@@ -1,26 +1,51 @@
 use crate::app::{Route, RECEIPT_STATE};
+use crate::core::summary::{SummaryOptions, SummaryStyle};
 use dioxus::prelude::*;
 
 #[component]
 pub fn DisplaySplits() -> Element {
     let nav = navigator();
-    if let Some(receipt) = RECEIPT_STATE.read().as_ref() {
+    let mut emoji = use_signal(|| false);
+    let mut itemized = use_signal(|| false);
+    let mut copied = use_signal(|| false);
+    if let Some(receipt) = RECEIPT_STATE.read().as_ref().map(|h| h.receipt()) {
         let mut header = receipt.shared_by.clone();
         header.insert(0, "Item Name".into());
         header.push("Total".into());
 
-        let (item_names, item_splits) = receipt.calculate_splits()?;
+        let (mut item_names, mut item_splits) = receipt.calculate_splits()?;
+        let (payment_names, payment_splits) = receipt.calculate_payment_splits()?;
+        item_names.extend(payment_names);
+        item_splits.extend(payment_splits);
 
         let rows: Vec<Vec<String>> = item_names
             .into_iter()
-            .zip(item_splits.into_iter())
+            .zip(item_splits)
             .map(|(item_name, splits)| {
-                let mut splits_as_str: Vec<String> = splits.iter().map(|x| x.to_string()).collect();
+                let mut splits_as_str: Vec<String> = splits
+                    .iter()
+                    .map(|x| receipt.locale.format_money(*x))
+                    .collect();
                 splits_as_str.insert(0, item_name.into());
                 splits_as_str
             })
             .collect();
 
+        let explanations = receipt
+            .shared_by
+            .iter()
+            .map(|person| receipt.explain(person))
+            .collect::<Result<Vec<_>, _>>()?;
+        let summary_options = SummaryOptions {
+            style: if emoji() {
+                SummaryStyle::Emoji
+            } else {
+                SummaryStyle::Plain
+            },
+            itemized: itemized(),
+        };
+        let summary = receipt.summarize(summary_options, &receipt.locale)?;
+
         rsx! {
             document::Title { "BorrowChecker | View" }
             header { class: "hero is-small is-primary",
@@ -57,6 +82,70 @@ pub fn DisplaySplits() -> Element {
                     }
                 }
             }
+            div { class: "is-flex is-justify-content-center is-align-items-center mb-4",
+                label { class: "checkbox mr-3",
+                    input {
+                        r#type: "checkbox",
+                        class: "mr-1",
+                        checked: emoji(),
+                        onchange: move |event| {
+                            emoji.set(event.checked());
+                            copied.set(false);
+                        },
+                    }
+                    "Emoji"
+                }
+                label { class: "checkbox mr-3",
+                    input {
+                        r#type: "checkbox",
+                        class: "mr-1",
+                        checked: itemized(),
+                        onchange: move |event| {
+                            itemized.set(event.checked());
+                            copied.set(false);
+                        },
+                    }
+                    "Per item"
+                }
+                button {
+                    class: "button is-link is-dark",
+                    onclick: move |_| {
+                        let summary = summary.clone();
+                        async move {
+                            // The browser may refuse to write to the clipboard, so only say
+                            // it was copied once it has been.
+                            let mut eval = document::eval(
+                                "try { await navigator.clipboard.writeText(await dioxus.recv()); \
+                                dioxus.send(true); } catch { dioxus.send(false); }",
+                            );
+                            let written = eval.send(summary).is_ok()
+                                && eval.recv::<bool>().await.unwrap_or(false);
+                            copied.set(written);
+                        }
+                    },
+                    if copied() {
+                        "Copied!"
+                    } else {
+                        "Copy summary"
+                    }
+                }
+            }
+            div { class: "section pt-0",
+                div { class: "container",
+                    for explanation in explanations.iter() {
+                        details { class: "box",
+                            summary { class: "has-text-weight-semibold",
+                                "How {explanation.person}'s share adds up"
+                            }
+                            ul { class: "mt-2",
+                                for line in explanation.describe(&receipt.locale) {
+                                    li { "{line}" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
             footer { class: "hero is-small is-primary",
                 div { class: "hero-body has-text-centered is-flex is-justify-content-center",
                     p { class: "subtitle is-size-7 mr-1", "Built with Rust & Dioxus | " }
@@ -1,11 +1,12 @@
 use crate::app::display::DisplaySplits;
 use crate::app::splash::CreateReceiptSplash;
 use crate::app::split::SplitUI;
-use crate::core::receipt::Receipt;
+use crate::core::history::EditHistory;
 use dioxus::prelude::*;
 
 static CSS: Asset = asset!("/assets/bulma.css");
-pub static RECEIPT_STATE: GlobalSignal<Option<Receipt>> = Signal::global(|| None);
+// The receipt being split, along with the edits made to it so that they can be undone.
+pub static RECEIPT_STATE: GlobalSignal<Option<EditHistory>> = Signal::global(|| None);
 
 #[derive(Routable, Clone, Debug)]
 #[rustfmt::skip]
@@ -1,5 +1,8 @@
 use crate::app::storage::use_persistent;
 use crate::app::{Route, RECEIPT_STATE};
+use crate::core::expression;
+use crate::core::history::EditHistory;
+use crate::core::locale::{Locale, LOCALES};
 use crate::core::receipt::Receipt;
 use dioxus::prelude::*;
 use dioxus_free_icons::icons::ld_icons;
@@ -9,6 +12,7 @@ use rust_decimal::prelude::*;
 #[component]
 pub fn CreateReceiptSplash() -> Element {
     let receipt_value: Signal<Option<Decimal>> = use_signal(|| None);
+    let locale: Signal<Locale> = use_signal(Locale::default);
     let people_input: Signal<Vec<String>> = use_signal(|| vec!["".to_string()]);
     let people_list: Memo<Vec<String>> = use_memo(move || {
         people_input
@@ -19,6 +23,15 @@ pub fn CreateReceiptSplash() -> Element {
             .collect()
     });
 
+    // Highlight the person the receipt cannot be created because of, if any.
+    let invalid_person: Memo<Option<String>> = use_memo(move || {
+        receipt_value().and_then(|value| {
+            Receipt::new(value, people_list().iter().map(|x| x.as_str()).collect())
+                .err()
+                .and_then(|error| error.context().person.clone())
+        })
+    });
+
     rsx! {
         document::Title { "BorrowChecker | Create" }
         header { class: "hero is-small is-primary",
@@ -28,12 +41,13 @@ pub fn CreateReceiptSplash() -> Element {
             }
         }
         div { class: "section is-small",
-            ReceiptValue { receipt_value }
+            ReceiptValue { receipt_value, locale }
+            ReceiptLocale { locale }
             hr {}
-            ReceiptPeopleList { people_input }
+            ReceiptPeopleList { people_input, invalid_person }
         }
         div { class: "section",
-            SubmitReceipt { receipt_value, people_list }
+            SubmitReceipt { receipt_value, people_list, locale }
             RetrieveCache { people_input }
         }
         footer { class: "hero is-small is-primary",
@@ -51,32 +65,75 @@ pub fn CreateReceiptSplash() -> Element {
 }
 
 #[component]
-fn ReceiptValue(mut receipt_value: Signal<Option<Decimal>>) -> Element {
+fn ReceiptValue(mut receipt_value: Signal<Option<Decimal>>, locale: Signal<Locale>) -> Element {
+    // The total may be arithmetic, so what was typed is kept apart from its value.
+    let mut typed_value = use_signal(|| {
+        receipt_value
+            .peek()
+            .map_or_else(String::new, |d| d.to_string())
+    });
     rsx! {
         div { class: "container is-fluid",
             input {
                 class: "input is-primary",
-                min: 0.01,
                 placeholder: "Enter receipt total",
-                step: "0.01",
                 required: "true",
-                r#type: "number",
-                inputmode: "decimal",
-                value: "{receipt_value.read().map_or_else(String::new, |d| d.to_string())}",
+                r#type: "text",
+                value: "{typed_value}",
                 oninput: move |event| {
-                    if let Ok(value) = event.value().parse::<Decimal>() {
-                        receipt_value.set(Some(value));
-                    } else {
-                        receipt_value.set(None);
-                    }
+                    receipt_value.set(expression::evaluate_in(&event.value(), &locale.read()).ok());
+                    typed_value.set(event.value());
                 },
             }
         }
     }
 }
 
+// How amounts are written, shown by an example of each locale, and their currency.
 #[component]
-fn ReceiptPeopleList(mut people_input: Signal<Vec<String>>) -> Element {
+fn ReceiptLocale(mut locale: Signal<Locale>) -> Element {
+    let example = dec![1234.5];
+    rsx! {
+        div { class: "container is-fluid mt-3",
+            div { class: "columns is-mobile",
+                div { class: "column",
+                    div { class: "select is-primary is-fullwidth",
+                        select {
+                            onchange: move |event| {
+                                if let Ok(parsed) = event.value().parse::<Locale>() {
+                                    let currency = locale.read().currency.clone();
+                                    locale.set(Locale { currency, ..parsed });
+                                }
+                            },
+                            for name in LOCALES {
+                                option { value: name,
+                                    "{name.parse::<Locale>().map(|l| l.format_amount(example)).unwrap_or_default()}"
+                                }
+                            }
+                        }
+                    }
+                }
+                div { class: "column is-one-third",
+                    input {
+                        class: "input is-primary",
+                        r#type: "text",
+                        placeholder: "currency",
+                        oninput: move |event| {
+                            let currency = event.value().trim().to_string();
+                            locale.with_mut(|l| l.currency = Some(currency).filter(|c| !c.is_empty()));
+                        },
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ReceiptPeopleList(
+    mut people_input: Signal<Vec<String>>,
+    invalid_person: Memo<Option<String>>,
+) -> Element {
     rsx! {
         div { class: "container is-fluid",
             for (idx , person) in people_input().iter().enumerate() {
@@ -84,7 +141,7 @@ fn ReceiptPeopleList(mut people_input: Signal<Vec<String>>) -> Element {
 
                     div { class: "column is-9",
                         input {
-                            class: "input is-primary",
+                            class: if invalid_person().as_ref() == Some(person) { "input is-danger" } else { "input is-primary" },
                             key: "people_input_text_{idx}",
                             r#type: "text",
                             minlength: 0,
@@ -114,7 +171,7 @@ fn ReceiptPeopleList(mut people_input: Signal<Vec<String>>) -> Element {
                                     icon: ld_icons::LdCircleX,
                                 }
                             }
-                        } else if person != "" {
+                        } else if !person.is_empty() {
                             button {
                                 class: "button is-primary is-dark is-rounded",
                                 key: "people_input_add_button_{idx}",
@@ -142,15 +199,17 @@ fn ReceiptPeopleList(mut people_input: Signal<Vec<String>>) -> Element {
 fn SubmitReceipt(
     receipt_value: Signal<Option<Decimal>>,
     people_list: Memo<Vec<String>>,
+    locale: Signal<Locale>,
 ) -> Element {
     let nav = navigator();
-    if !receipt_value().is_none() && people_list.read().len() > 0 {
+    if receipt_value().is_some() && !people_list.read().is_empty() {
         let generated_receipt = Receipt::new(
             receipt_value().unwrap(),
             people_list().iter().map(|x| x.as_str()).collect(),
         );
         match generated_receipt {
-            Ok(valid_receipt) => {
+            Ok(mut valid_receipt) => {
+                valid_receipt.locale = locale();
                 rsx! {
                     div { class: "container is-fluid",
                         button {
@@ -158,7 +217,7 @@ fn SubmitReceipt(
                             key: "submit_receipt",
                             onclick: move |_| {
                                 set_people(people_list().clone());
-                                *RECEIPT_STATE.write() = Some(valid_receipt.clone());
+                                *RECEIPT_STATE.write() = Some(EditHistory::new(valid_receipt.clone()));
                                 nav.push(Route::SplitUI);
                             },
                             "Submit"
@@ -207,7 +266,7 @@ fn set_people(new_people_list: Vec<String>) {
 fn RetrieveCache(people_input: Signal<Vec<String>>) -> Element {
     let cache_people_list = retrieve_people();
     rsx! {
-        if cache_people_list.len() > 0 {
+        if !cache_people_list.is_empty() {
             hr {}
             div { class: "panel-heading", "Or pick from recently used groups:" }
             for (idx , people) in cache_people_list.clone().into_iter().rev().enumerate() {
@@ -1,4 +1,8 @@
 use crate::app::{Route, RECEIPT_STATE};
+use crate::core::expression;
+use crate::core::history::ReceiptEdit;
+use crate::core::locale::Locale;
+use crate::core::validation::{Severity, ValidationIssue};
 use dioxus::prelude::*;
 use dioxus_free_icons::icons::ld_icons;
 use dioxus_free_icons::Icon;
@@ -7,14 +11,21 @@ use rust_decimal::prelude::*;
 #[component]
 pub fn SplitUI() -> Element {
     let nav = navigator();
-    if let Some(receipt) = RECEIPT_STATE.read().as_ref() {
+    if let Some(history) = RECEIPT_STATE.read().as_ref() {
+        let receipt = history.receipt();
+        let (can_undo, can_redo) = (history.can_undo(), history.can_redo());
         let (_, balance) = receipt.get_itemized_total_and_leftover();
         let item_count = receipt.items.len();
+        let receipt_issues: Vec<ValidationIssue> = receipt
+            .validate()
+            .into_iter()
+            .filter(|issue| issue.item_idx.is_none())
+            .collect();
         rsx! {
             document::Title { "BorrowChecker | Split" }
             header { class: "hero is-small is-primary",
                 div { class: "hero-body has-text-centered",
-                    ColorBalanceTitle { balance }
+                    ColorBalanceTitle { balance, locale: receipt.locale.clone() }
                 }
             }
             div { class: "section",
@@ -22,6 +33,9 @@ pub fn SplitUI() -> Element {
                     for item_idx in 0..item_count {
                         SplitItemUI { item_idx }
                     }
+                    if !receipt.items.is_empty() {
+                        ValidationIssues { issues: receipt_issues }
+                    }
                 }
                 div { class: "is-flex is-justify-content-center",
                     div { class: "buttons",
@@ -30,14 +44,14 @@ pub fn SplitUI() -> Element {
                                 class: "button is-primary is-dark",
                                 key: "item_add_button",
                                 onclick: move |_| {
-                                    if let Some(r) = RECEIPT_STATE.write().as_mut() {
-                                        let people_list = r.shared_by.clone();
-                                        r.add_item_split_by_ratio(
-                                                Decimal::ZERO,
-                                                format!("Item {}", item_count + 1),
-                                                people_list,
-                                                None,
-                                            )
+                                    if let Some(h) = RECEIPT_STATE.write().as_mut() {
+                                        let people_list = h.receipt().shared_by.clone();
+                                        h.apply(ReceiptEdit::AddItem {
+                                                value: Decimal::ZERO,
+                                                name: format!("Item {}", item_count + 1),
+                                                shared_by: people_list,
+                                                share_ratio: None,
+                                            })
                                             .unwrap();
                                     }
                                 },
@@ -48,11 +62,44 @@ pub fn SplitUI() -> Element {
                                     icon: ld_icons::LdBookPlus,
                                 }
                                 span { class: "ml-2", "Add Item" }
-                            
                             }
                         }
                         div {
-                            if receipt.items.len() > 0 && receipt.items.iter().all(|x| x.value > Decimal::ZERO)
+                            button {
+                                class: "button is-primary is-outlined",
+                                key: "undo_button",
+                                disabled: !can_undo,
+                                onclick: move |_| {
+                                    if let Some(h) = RECEIPT_STATE.write().as_mut() {
+                                        let _ = h.undo();
+                                    }
+                                },
+                                Icon {
+                                    width: 24,
+                                    height: 24,
+                                    icon: ld_icons::LdUndo2,
+                                }
+                            }
+                        }
+                        div {
+                            button {
+                                class: "button is-primary is-outlined",
+                                key: "redo_button",
+                                disabled: !can_redo,
+                                onclick: move |_| {
+                                    if let Some(h) = RECEIPT_STATE.write().as_mut() {
+                                        let _ = h.redo();
+                                    }
+                                },
+                                Icon {
+                                    width: 24,
+                                    height: 24,
+                                    icon: ld_icons::LdRedo2,
+                                }
+                            }
+                        }
+                        div {
+                            if !receipt.items.is_empty() && !receipt.has_validation_errors()
                                 && receipt.calculate_splits().is_ok()
                             {
                                 button {
@@ -93,13 +140,14 @@ pub fn SplitUI() -> Element {
 }
 
 #[component]
-fn ColorBalanceTitle(balance: Decimal) -> Element {
+fn ColorBalanceTitle(balance: Decimal, locale: Locale) -> Element {
+    let amount = locale.format_money(balance);
     rsx! {
         if balance > Decimal::ZERO {
-            p { class: "title has-text-dark is-size-4", "+{balance}" }
+            p { class: "title has-text-dark is-size-4", "+{amount}" }
             p { class: "subtitle is-size-5", "left to balance" }
         } else if balance < Decimal::ZERO {
-            p { class: "title has-text-danger is-size-4", "Remaining: {balance}" }
+            p { class: "title has-text-danger is-size-4", "Remaining: {amount}" }
             p { class: "subtitle is-size-6", "Item total exceeds receipt total." }
         } else {
             p { class: "title has-text-link  is-size-4", "0" }
@@ -108,27 +156,58 @@ fn ColorBalanceTitle(balance: Decimal) -> Element {
     }
 }
 
+#[component]
+fn ValidationIssues(issues: Vec<ValidationIssue>) -> Element {
+    rsx! {
+        for issue in issues.iter() {
+            p { class: if issue.severity == Severity::Error { "help is-danger" } else { "help is-warning" },
+                "{issue.message}"
+            }
+        }
+    }
+}
+
 #[component]
 fn SplitItemUI(item_idx: usize) -> Element {
-    let people_list = (*RECEIPT_STATE.read()).as_ref().unwrap().shared_by.clone();
+    let people_list = (*RECEIPT_STATE.read())
+        .as_ref()
+        .unwrap()
+        .receipt()
+        .shared_by
+        .clone();
 
+    let locale = RECEIPT_STATE
+        .read()
+        .as_ref()
+        .map(|h| h.receipt().locale.clone())
+        .unwrap_or_default();
     let (item_name, item_value, item_shared_by) = &RECEIPT_STATE
         .read()
         .as_ref()
-        .and_then(|r| r.items.get(item_idx))
-        .map(|item| {
-            (
-                item.name.clone(),
-                item.value.clone(),
-                item.shared_by.clone(),
-            )
+        .and_then(|h| h.receipt().items.get(item_idx))
+        .map(|item| (item.name.clone(), item.value, item.shared_by.clone()))
+        .unwrap_or_default();
+
+    let item_issues: Vec<ValidationIssue> = RECEIPT_STATE
+        .read()
+        .as_ref()
+        .map(|h| {
+            h.receipt()
+                .validate()
+                .into_iter()
+                .filter(|issue| issue.item_idx == Some(item_idx))
+                .collect()
         })
         .unwrap_or_default();
 
-    let item_value = if item_value > &Decimal::ZERO {
-        item_value.to_string()
-    } else {
-        "-".to_string()
+    // What was typed in the value, which may be arithmetic, and what it evaluated to. It
+    // is shown while the item still has that value, and not once the value is changed
+    // elsewhere, such as by an undo.
+    let mut typed_value: Signal<Option<(String, Decimal)>> = use_signal(|| None);
+    let item_value = match typed_value() {
+        Some((typed, value)) if &value == item_value => typed,
+        _ if !item_value.is_zero() => locale.format_amount(*item_value),
+        _ => "-".to_string(),
     };
 
     if true {
@@ -141,10 +220,12 @@ fn SplitItemUI(item_idx: usize) -> Element {
                         r#type: "text",
                         value: "{item_name}",
                         oninput: move |evt| {
-                            if let Some(r) = RECEIPT_STATE.write().as_mut() {
-                                if let Some(item) = r.items.get_mut(item_idx) {
-                                    item.name = evt.value();
-                                }
+                            if let Some(h) = RECEIPT_STATE.write().as_mut() {
+                                let _ = h
+                                    .amend(ReceiptEdit::EditItemName {
+                                        item_idx,
+                                        name: evt.value(),
+                                    });
                             }
                         },
                         placeholder: "item name",
@@ -154,21 +235,15 @@ fn SplitItemUI(item_idx: usize) -> Element {
                     input {
                         class: "input is-primary",
                         key: "item_input_value_{item_idx}",
-                        min: "0.00",
-                        step: "0.01",
-                        inputmode: "decimal",
                         required: "true",
-                        r#type: "number",
+                        r#type: "text",
                         value: "{item_value}",
                         oninput: move |evt| {
-                            if let Some(r) = RECEIPT_STATE.write().as_mut() {
-                                if let Some(item) = r.items.get_mut(item_idx) {
-                                    if let Ok(valid_decimal) = evt.value().parse::<Decimal>() {
-                                        item.value = valid_decimal;
-                                    } else {
-                                        item.value = Decimal::ZERO;
-                                    }
-                                }
+                            let value = expression::evaluate_in(&evt.value(), &locale)
+                                .unwrap_or(Decimal::ZERO);
+                            typed_value.set(Some((evt.value(), value)));
+                            if let Some(h) = RECEIPT_STATE.write().as_mut() {
+                                let _ = h.amend(ReceiptEdit::EditItemValue { item_idx, value });
                             }
                         },
                         placeholder: "amount",
@@ -182,28 +257,37 @@ fn SplitItemUI(item_idx: usize) -> Element {
                             class: if item_shared_by.contains(&person) { "button is-primary is-dark is-fullwidth" } else { "button is-primary is-outlined is-dark is-fullwidth" },
                             key: "item_{item_idx}_person_{person_idx}",
                             onclick: move |_| {
-                                if let Some(r) = RECEIPT_STATE.write().as_mut() {
-                                    if let Some(item) = r.items.get_mut(item_idx) {
-                                        if item.shared_by.contains(&person) && item.shared_by.len() > 1 {
-                                            let shared_by_idx = item
-                                                .shared_by
-                                                .iter()
-                                                .position(|name| *name == *person)
-                                                .unwrap();
-                                            item.shared_by.remove(shared_by_idx);
-                                            item.share_ratio.remove(shared_by_idx);
-                                        } else {
-                                            item.shared_by.push(person.clone());
-                                            item.share_ratio.push(Decimal::ONE);
-                                        }
-                                    }
+                                if let Some(h) = RECEIPT_STATE.write().as_mut() {
+                                    // The last person sharing an item cannot be toggled off.
+                                    let _ = h
+                                        .apply(ReceiptEdit::ToggleSharer {
+                                            item_idx,
+                                            person: person.clone(),
+                                        });
                                 }
                             },
                             "{person}"
                         }
                     }
                 }
+                div {
+                    button {
+                        class: "button is-danger is-outlined",
+                        key: "item_{item_idx}_remove",
+                        onclick: move |_| {
+                            if let Some(h) = RECEIPT_STATE.write().as_mut() {
+                                let _ = h.apply(ReceiptEdit::RemoveItem { item_idx });
+                            }
+                        },
+                        Icon {
+                            width: 24,
+                            height: 24,
+                            icon: ld_icons::LdTrash2,
+                        }
+                    }
+                }
             }
+            ValidationIssues { issues: item_issues }
             hr {}
         }
     } else {
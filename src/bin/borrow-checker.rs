@@ -0,0 +1,10 @@
+// Command-line companion to the web app. It only needs the core receipt model, so it
+// builds with or without any of the Dioxus platform features.
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> std::process::ExitCode {
+    borrow_checker::cli::command::run()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -1,41 +1,630 @@
-use crate::core::receipt::{Receipt, SplittingError};
-use std::env;
+use crate::cli::display::{OutputFormat, TableStyle};
+use crate::cli::store::{self, Store};
+use crate::cli::tokenizer::{self, unquote};
+use crate::cli::{receipt_file, records};
+use crate::core::alias::AliasTable;
+use crate::core::locale::Locale;
+use crate::core::receipt::{ErrorContext, Receipt, SplittingError};
+use crate::core::summary::SummaryStyle;
+use std::path::{Path, PathBuf};
 
-// Super-basic parsing, advanced parsing packages are not needed
-pub fn parse_args() -> Result<Receipt, SplittingError> {
-    let args: Vec<String> = env::args().collect();
+// Options about what to do with the splits, rather than about the receipt.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CliOptions {
+    pub output: OutputFormat,
+    pub style: TableStyle,
+    // Where to also write the splits to, if anywhere.
+    pub write: Option<PathBuf>,
+    // The name to save the split to the history under, if it is to be saved.
+    pub save: Option<String>,
+    // The abbreviation of the person whose share is to be explained, if anyone's.
+    pub explain: Option<String>,
+    // How to write a summary to send to the people sharing the receipt, in place of the
+    // splits, if one is to be written.
+    pub summary: Option<SummaryStyle>,
+    // Whether the summary lists what each person owes for each item.
+    pub itemize: bool,
+}
+
+impl CliOptions {
+    // Returns None if the option is not about what to do with the splits.
+    pub(crate) fn parse_option(
+        &mut self,
+        option: &str,
+        value: &str,
+    ) -> Option<Result<(), SplittingError>> {
+        match option {
+            "output" => Some(value.parse().map(|output| self.output = output)),
+            "layout" => Some(value.parse().map(|layout| self.style.layout = layout)),
+            "color" | "colour" => Some(value.parse().map(|color| self.style.color = color)),
+            "compact" => {
+                self.style.compact = true;
+                Some(Ok(()))
+            }
+            "write" => {
+                self.write = Some(PathBuf::from(value));
+                Some(Ok(()))
+            }
+            "save" => {
+                self.save = Some(value.to_string());
+                Some(Ok(()))
+            }
+            "summary" => Some(value.parse().map(|style| self.summary = Some(style))),
+            "itemize" => {
+                self.itemize = true;
+                Some(Ok(()))
+            }
+            "explain" => {
+                self.explain = Some(value.to_string());
+                Some(Ok(()))
+            }
+            _ => None,
+        }
+    }
+}
+
+// Options that are given without a value.
+pub(crate) const FLAGS: [&str; 2] = ["compact", "itemize"];
 
-    // dbg!(&args);
+// An option and its value, along with where it was found: the index of the argument,
+// or the line of a receipt file and the offset of the value within it. Documents
+// without line information have no location.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct OptionEntry {
+    pub location: Option<usize>,
+    pub offset: usize,
+    pub option: String,
+    pub value: String,
+    // Whether the option is known to be an item's name, even if it is also the name of
+    // an option, such as an item called 'Tax'.
+    pub is_item: bool,
+}
+
+impl OptionEntry {
+    pub(crate) fn new(location: Option<usize>, option: &str, value: &str) -> Self {
+        OptionEntry {
+            location,
+            offset: 0,
+            option: option.to_string(),
+            value: value.to_string(),
+            is_item: false,
+        }
+    }
+
+    pub(crate) fn for_item(mut self) -> Self {
+        self.is_item = true;
+        self
+    }
+
+    pub(crate) fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    fn locate(&self, error: SplittingError) -> SplittingError {
+        match self.location {
+            // Errors in another file, such as one of items, are located within it.
+            Some(_) if error.context().file.is_some() => error,
+            Some(location) => error.shift_span(self.offset).in_argument(location),
+            None => error,
+        }
+    }
+}
 
+// Options about how amounts are written. As even the receipt's own pattern is written
+// in its locale, these are read before anything else. Returns None if the option is not
+// one of them.
+fn parse_locale_setting(
+    locale: &mut Locale,
+    option: &str,
+    value: &str,
+) -> Option<Result<(), SplittingError>> {
+    match option {
+        "locale" => Some(value.parse().map(|parsed| *locale = parsed)),
+        "delimiter" => Some(locale.set_delimiter(value)),
+        "currency" => {
+            locale.currency = Some(value.to_string());
+            Some(Ok(()))
+        }
+        _ => None,
+    }
+}
+
+// Options that change how every pattern is read, and so apply wherever they appear.
+// Returns None if the option is not one of them.
+fn parse_setting(
+    receipt: &mut Receipt,
+    option: &str,
+    value: &str,
+) -> Option<Result<(), SplittingError>> {
+    match option {
+        "match" => Some(value.parse().map(|matching| receipt.matching = matching)),
+        "alias" => {
+            let mut aliases = AliasTable::new();
+            Some(
+                aliases
+                    .parse_insert(value)
+                    .map(|_| receipt.seed_aliases(&aliases)),
+            )
+        }
+        "aliases" => Some(
+            receipt_file::load_alias_file(Path::new(value))
+                .map(|aliases| receipt.seed_aliases(&aliases)),
+        ),
+        // Already applied, before the receipt was created.
+        "locale" | "delimiter" | "currency" => Some(Ok(())),
+        _ => None,
+    }
+}
+
+// Options that are not item names. Proportional items are only added once every other
+// item is known, as their split depends on the other items.
+fn parse_option<'a>(
+    receipt: &mut Receipt,
+    options: &mut CliOptions,
+    entry: &'a OptionEntry,
+    proportional_items: &mut Vec<(&'a OptionEntry, String, String)>,
+) -> Result<(), SplittingError> {
+    let value = entry.value.as_str();
+    if entry.is_item {
+        return receipt.parse_add_named_item(&entry.option, value);
+    }
+    if let Some(result) = options.parse_option(&entry.option, value) {
+        return result;
+    }
+    match entry.option.as_str() {
+        "paid" => receipt.parse_add_payment(value),
+        "items" => records::add_item_records(receipt, value),
+        "tax" | "tip" | "service" => {
+            let mut name = entry.option.clone();
+            name[..1].make_ascii_uppercase();
+            proportional_items.push((entry, name, value.to_string()));
+            Ok(())
+        }
+        "prop" => match tokenizer::split_once_raw(value, |c| c == '=')? {
+            Some((name, _, pattern)) => {
+                proportional_items.push((entry, unquote(name), pattern.to_string()));
+                Ok(())
+            }
+            None => Err(SplittingError::InvalidArgument(
+                ErrorContext::new(format!(
+                    "A proportional item must have pattern 'Name=Amount[%][,Person_1,...]', \
+                    but you have {}",
+                    value
+                ))
+                .with_value(value)
+                .with_span(0, value.len())
+                .with_help("put an equals sign after the item's name, as in 'Delivery=5'"),
+            )),
+        },
+        item_name => receipt.parse_add_named_item(item_name, value),
+    }
+}
+
+// The receipt's pattern with saved groups in place of their @name, and the aliases of
+// those groups. A group given before the pattern, as in '@flatmates 300', is its option,
+// and shares the receipt with anyone in the pattern. Groups are looked up in the store,
+// or in the user's own if there is none, which is only opened once a group is used.
+fn expand_receipt_pattern(
+    entry: &OptionEntry,
+    delimiter: char,
+    store: Option<&Store>,
+) -> Result<(String, AliasTable), SplittingError> {
+    let group = |name: &str| match store {
+        Some(store) => store.group(name),
+        None => Store::open()?.group(name),
+    };
+    let (mut pattern, mut aliases) =
+        store::expand_groups(&entry.value, delimiter, group).map_err(|e| entry.locate(e))?;
+    if let Some(name) = entry.option.strip_prefix('@') {
+        let prefix = group(name).map_err(|e| {
+            let e = e.with_span(0, entry.option.len());
+            match entry.location {
+                Some(location) => e.in_argument(location - 1),
+                None => e,
+            }
+        })?;
+        pattern.push(delimiter);
+        pattern.push_str(&prefix.people_pattern(delimiter));
+        aliases.extend(&prefix.aliases);
+    }
+    Ok((pattern, aliases))
+}
+
+// Create the receipt from its 'Total,Person_1,...' pattern and add every item, payment
+// and option to it, in order.
+pub(crate) fn build_receipt(
+    receipt_pattern: &OptionEntry,
+    entries: &[OptionEntry],
+    store: Option<&Store>,
+) -> Result<(Receipt, CliOptions), SplittingError> {
+    build_receipt_in(receipt_pattern, entries, store, Locale::default())
+}
+
+// Build the receipt with the options applied to a locale other than the default one.
+fn build_receipt_in(
+    receipt_pattern: &OptionEntry,
+    entries: &[OptionEntry],
+    store: Option<&Store>,
+    mut locale: Locale,
+) -> Result<(Receipt, CliOptions), SplittingError> {
+    // A locale is applied before any delimiter or currency, wherever it appears, so that
+    // it does not reset them.
+    let mut locale_entries: Vec<&OptionEntry> =
+        entries.iter().filter(|entry| !entry.is_item).collect();
+    locale_entries.sort_by_key(|entry| entry.option != "locale");
+    for entry in locale_entries {
+        if let Some(result) = parse_locale_setting(&mut locale, &entry.option, &entry.value) {
+            result.map_err(|e| entry.locate(e))?;
+        }
+    }
+
+    let (pattern, aliases) = expand_receipt_pattern(receipt_pattern, locale.delimiter, store)?;
+    let mut receipt = Receipt::parse_create_receipt_in(&pattern, locale)
+        .map_err(|e| receipt_pattern.locate(e))?;
+    receipt.seed_aliases(&aliases);
+    let mut options = CliOptions::default();
+    let mut proportional_items = Vec::new();
+    let mut rest: Vec<&OptionEntry> = Vec::new();
+    for entry in entries {
+        if entry.is_item {
+            rest.push(entry);
+            continue;
+        }
+        match parse_setting(&mut receipt, &entry.option, &entry.value) {
+            Some(result) => result.map_err(|e| entry.locate(e))?,
+            None => rest.push(entry),
+        }
+    }
+    for entry in rest {
+        parse_option(&mut receipt, &mut options, entry, &mut proportional_items)
+            .map_err(|e| entry.locate(e))?;
+    }
+    for (entry, name, pattern) in proportional_items {
+        receipt
+            .parse_add_proportional_item(&name, &pattern)
+            .map_err(|e| entry.locate(e))?;
+    }
+    Ok((receipt, options))
+}
+
+// Options that can follow the receipt's pattern when starting an interactive mode.
+const STARTING_OPTIONS: &[&str] = &["locale", "delimiter", "currency"];
+
+// The receipt that an interactive mode starts from: its pattern, followed only by options
+// about how amounts are written, as in '-i 300;Al;B --locale de'. As with parse_args, the
+// first argument is the mode. Returns None if there is no pattern.
+pub(crate) fn parse_starting_receipt(
+    args: &[String],
+    locale: Locale,
+) -> Result<Option<Receipt>, SplittingError> {
+    let Some(pattern) = args.get(1) else {
+        return Ok(None);
+    };
+    let receipt_pattern = OptionEntry::new(Some(1), "", pattern);
+    let mut entries: Vec<OptionEntry> = Vec::new();
+    let mut rest = args.iter().enumerate().skip(2);
+    while let Some((arg_idx, arg)) = rest.next() {
+        let option = arg
+            .strip_prefix("--")
+            .filter(|option| STARTING_OPTIONS.contains(option));
+        match (option, rest.next()) {
+            (Some(option), Some((value_idx, value))) => {
+                entries.push(OptionEntry::new(Some(value_idx), option, value))
+            }
+            (Some(_), None) => {
+                return Err(SplittingError::InvalidArgument(
+                    ErrorContext::new(format!("{} must be followed by its value", arg))
+                        .with_value(arg)
+                        .with_span(0, arg.len())
+                        .with_help("follow it with its value"),
+                )
+                .in_argument(arg_idx))
+            }
+            (None, _) => {
+                return Err(SplittingError::InvalidArgument(
+                    ErrorContext::new(format!(
+                        "{} cannot follow the receipt's pattern here, as items are added \
+                        once the receipt is started",
+                        arg
+                    ))
+                    .with_value(arg)
+                    .with_span(0, arg.len())
+                    .with_help("only --locale, --delimiter and --currency can follow it"),
+                )
+                .in_argument(arg_idx))
+            }
+        }
+    }
+    build_receipt_in(&receipt_pattern, &entries, None, locale).map(|(receipt, _)| Some(receipt))
+}
+
+// Super-basic parsing, advanced parsing packages are not needed. As with env::args,
+// the first argument is the name of the program.
+pub fn parse_args(args: &[String]) -> Result<(Receipt, CliOptions), SplittingError> {
+    parse_args_in(args, None)
+}
+
+// Parse the arguments with the groups of a store, rather than of the user's own.
+pub(crate) fn parse_args_in(
+    args: &[String],
+    store: Option<&Store>,
+) -> Result<(Receipt, CliOptions), SplittingError> {
     if args.len() < 2 {
-        return Err(SplittingError::InvalidArgument(format!(
+        Err(SplittingError::InvalidArgument(ErrorContext::new(
             "You have specified only the receipt's total value and people sharing it \
-            but not any item within it to split. Please do so"
-        )));
+            but not any item within it to split. Please do so",
+        )))
     } else {
-        let mut receipt = Receipt::parse_create_receipt(&args[1])?;
+        // A saved group can come before the receipt's pattern, as in '@flatmates 300'.
+        let receipt_pattern = if !args[1].starts_with('@') {
+            OptionEntry::new(Some(1), "", &args[1])
+        } else if let Some(total) = args.get(2) {
+            OptionEntry::new(Some(2), &args[1], total)
+        } else {
+            return Err(SplittingError::InvalidArgument(
+                ErrorContext::new(format!(
+                    "{} must be followed by the receipt's total",
+                    args[1]
+                ))
+                .with_span(0, args[1].len())
+                .with_help(format!("follow it with the total, as in '{} 300'", args[1])),
+            )
+            .in_argument(1));
+        };
         let mut curr_arg: Option<&str> = None;
-        for (arg_idx, arg) in args[2..].iter().enumerate() {
+        // Names after a single dash, or after a lone '--', are always items, even if they
+        // are also the name of an option, so that '-Tip 5,Al' is an item called Tip.
+        let mut is_item = false;
+        let mut after_marker = false;
+        let mut entries: Vec<OptionEntry> = Vec::new();
+        let first_option = receipt_pattern.location.unwrap_or(1) + 1;
+        for (arg_idx, arg) in args.iter().enumerate().skip(first_option) {
             if curr_arg.is_none() {
-                if arg.starts_with("--") {
-                    curr_arg = Some(&arg[2..]);
-                } else if arg.starts_with("-") {
-                    curr_arg = Some(&arg[1..]);
-                    continue;
+                if after_marker {
+                    // Names after a lone '--' are taken as they are, dashes and all.
+                    curr_arg = Some(arg);
+                    is_item = true;
+                } else if arg == "--" {
+                    after_marker = true;
+                } else if let Some(flag) =
+                    arg.strip_prefix("--").filter(|flag| FLAGS.contains(flag))
+                {
+                    entries.push(OptionEntry::new(Some(arg_idx), flag, ""));
+                } else if let Some(name) = arg.strip_prefix("--") {
+                    curr_arg = Some(name);
+                    is_item = false;
+                } else if let Some(name) = arg.strip_prefix("-") {
+                    curr_arg = Some(name);
+                    is_item = true;
                 } else {
-                    return Err(SplittingError::InvalidArgument(format!(
-                        "Argument {} is expected (in this case) to be an item name, \
-                        and must be prefixed with a dash (-) or a double dash (--). Currently, \
-                        it is {}",
-                        arg_idx + 1,
-                        arg
-                    )));
+                    return Err(SplittingError::InvalidArgument(
+                        ErrorContext::new(format!(
+                            "{} is expected (in this case) to be an item name, and must be \
+                            prefixed with a dash (-) or a double dash (--)",
+                            arg
+                        ))
+                        .with_value(arg)
+                        .with_span(0, arg.len())
+                        .with_help(format!(
+                            "write -{} if it is an item's name, or put '--' before item names \
+                            that start without a dash",
+                            arg
+                        )),
+                    )
+                    .in_argument(arg_idx));
                 }
             } else {
-                receipt.parse_add_named_item(curr_arg.unwrap(), arg)?;
+                let entry = OptionEntry::new(Some(arg_idx), curr_arg.unwrap(), arg);
+                entries.push(if is_item { entry.for_item() } else { entry });
                 curr_arg = None
             }
         }
-        Ok(receipt)
+        if let Some(item_name) = curr_arg {
+            let arg_idx = args.len() - 1;
+            return Err(SplittingError::InvalidArgument(
+                ErrorContext::new(format!(
+                    "{} must be followed by its pattern 'Value,Person_1[,Person_2,...]'",
+                    item_name
+                ))
+                .with_value(&args[arg_idx])
+                .with_span(0, args[arg_idx].len())
+                .with_help(format!(
+                    "follow it with its pattern, as in '{} 200,Al,B'",
+                    args[arg_idx]
+                )),
+            )
+            .in_argument(arg_idx));
+        }
+        build_receipt(&receipt_pattern, &entries, store)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::arg_parser::{parse_args, parse_args_in, parse_starting_receipt};
+    use crate::cli::display::OutputFormat;
+    use crate::cli::store::Store;
+    use crate::core::locale::Locale;
+    use crate::core::receipt::{InputSpan, SplittingError};
+    use crate::utils;
+    use rust_decimal::prelude::*;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn test_parse_args() {
+        let args = utils::strs_to_strings(vec![
+            "borrow-checker",
+            "300,Alice,Bob",
+            "--Food",
+            "200,Al,B",
+            "-Drinks",
+            "50,Al",
+            "--paid",
+            "B,300",
+        ]);
+        let (receipt, options) = parse_args(&args).unwrap();
+        assert_eq!(receipt.items.len(), 2);
+        assert_eq!(options.output, OutputFormat::Table);
+
+        // Proportional items are added after every other item, wherever they appear.
+        let mut with_tax = args.clone();
+        with_tax.insert(2, "--tax".into());
+        with_tax.insert(3, "10%".into());
+        with_tax.extend(utils::strs_to_strings(vec!["--prop", "Delivery=5,B"]));
+        with_tax.extend(utils::strs_to_strings(vec!["--output", "csv"]));
+        let (receipt, options) = parse_args(&with_tax).unwrap();
+        assert_eq!(options.output, OutputFormat::Csv);
+        assert_eq!(receipt.items[2].name, "Tax (10%)");
+        assert_eq!(receipt.items[2].value, dec![25]);
+        assert_eq!(receipt.items[3].name, "Delivery");
+        assert_eq!(receipt.items[1].shared_by, vec!["Alice"]);
+        assert_eq!(receipt.payments[0].amount, dec![300]);
+
+        // Only a double dash makes an option, so single-dash names are always items.
+        let mut single_dash = args.clone();
+        single_dash.extend(utils::strs_to_strings(vec![
+            "-write", "20,Al", "-tip", "5,B",
+        ]));
+        let (receipt, options) = parse_args(&single_dash).unwrap();
+        assert_eq!(options.write, None);
+        assert_eq!(receipt.items[2].name, "write");
+        assert_eq!(receipt.items[3].name, "tip");
+        assert!(!receipt.items[3].is_prop_dist);
+
+        // An item name without its pattern is an error, not silently dropped.
+        let error = parse_args(&args[..3]).unwrap_err();
+        assert!(matches!(error, SplittingError::InvalidArgument(_)));
+        assert_eq!(error.context().argument, Some(2));
+
+        // Settings apply to items before them too.
+        let mut with_settings = args.clone();
+        with_settings[3] = "200,al,bobby".into();
+        let error = parse_args(&with_settings).unwrap_err();
+        assert!(matches!(error, SplittingError::InvalidAbbreviation(_)));
+        assert_eq!(error.context().argument, Some(3));
+        with_settings.extend(utils::strs_to_strings(vec![
+            "--alias",
+            "bobby=Bob",
+            "--match",
+            "case-insensitive",
+        ]));
+        let (receipt, _) = parse_args(&with_settings).unwrap();
+        assert_eq!(receipt.items[0].shared_by, vec!["Alice", "Bob"]);
+
+        // After a lone '--', names are items even if they start with a dash or are the
+        // name of an option.
+        let mut after_marker = args.clone();
+        after_marker.extend(utils::strs_to_strings(vec![
+            "--",
+            "-20% promo",
+            "-20,Al,B",
+            "tax",
+            "\"10\",\"B\"",
+        ]));
+        let (receipt, _) = parse_args(&after_marker).unwrap();
+        assert_eq!(receipt.items[2].name, "-20% promo");
+        assert_eq!(receipt.items[2].value, dec![-20]);
+        assert_eq!(receipt.items[3].name, "tax");
+        assert_eq!(receipt.items[3].shared_by, vec!["Bob"]);
+
+        // Amounts in the locale, with patterns separated by its delimiter, even when the
+        // locale comes last.
+        let localized = utils::strs_to_strings(vec![
+            "borrow-checker",
+            "1.234,50;Alice;Bob",
+            "--Food",
+            "1.000;Al;B",
+            "--locale",
+            "de",
+            "--currency",
+            "€",
+        ]);
+        let (receipt, _) = parse_args(&localized).unwrap();
+        assert_eq!(receipt.value, dec![1234.50]);
+        assert_eq!(receipt.items[0].value, dec![1000]);
+        assert_eq!(receipt.locale.currency.as_deref(), Some("€"));
+
+        let mut bad_output = args.clone();
+        bad_output.extend(utils::strs_to_strings(vec!["--output", "xml"]));
+        let error = parse_args(&bad_output).unwrap_err();
+        assert!(matches!(error, SplittingError::InvalidArgument(_)));
+        assert_eq!(error.context().argument, Some(9));
+    }
+
+    #[test]
+    fn share_receipts_with_saved_groups() {
+        let dir = env::temp_dir().join(format!("borrow-checker-groups-{}", std::process::id()));
+        let store = Store::at(&dir);
+        store
+            .save_group("flatmates", utils::strs_to_strings(vec!["Alice", "Bob"]))
+            .unwrap();
+        store
+            .update_aliases("flatmates", |aliases| aliases.parse_insert("Ally=Alice"))
+            .unwrap();
+
+        let args = utils::strs_to_strings(vec![
+            "borrow-checker",
+            "@flatmates",
+            "300,Dave",
+            "--Food",
+            "300,Ally,B,D",
+        ]);
+        let (receipt, _) = parse_args_in(&args, Some(&store)).unwrap();
+        assert_eq!(receipt.shared_by, vec!["Dave", "Alice", "Bob"]);
+        assert_eq!(receipt.items[0].shared_by, vec!["Alice", "Bob", "Dave"]);
+
+        let args = utils::strs_to_strings(vec![
+            "borrow-checker",
+            "300,@flatmates",
+            "--Food",
+            "300,A,B",
+        ]);
+        assert_eq!(
+            parse_args_in(&args, Some(&store)).unwrap().0.shared_by,
+            vec!["Alice", "Bob"]
+        );
+
+        let args =
+            utils::strs_to_strings(vec!["borrow-checker", "@work", "300", "--Food", "300,A"]);
+        let error = parse_args_in(&args, Some(&store)).unwrap_err();
+        assert_eq!(error.context().argument, Some(1));
+        assert_eq!(error.context().span, Some(InputSpan { start: 0, end: 5 }));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn start_from_a_receipt_in_its_locale() {
+        let args = utils::strs_to_strings(vec!["-i"]);
+        assert!(parse_starting_receipt(&args, Locale::default())
+            .unwrap()
+            .is_none());
+
+        let args = utils::strs_to_strings(vec!["-i", "12,50;Al;B", "--locale", "de"]);
+        let receipt = parse_starting_receipt(&args, Locale::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(receipt.value, Decimal::new(1250, 2));
+        assert_eq!(receipt.shared_by, ["Al", "B"]);
+
+        // Without any option, the receipt is read in the locale it is given.
+        let args = utils::strs_to_strings(vec!["-i", "12,50;Al;B", "--currency", "€"]);
+        let receipt = parse_starting_receipt(&args, "de".parse().unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(receipt.value, Decimal::new(1250, 2));
+        assert_eq!(receipt.locale.currency.as_deref(), Some("€"));
+
+        for (args, argument) in [
+            (vec!["-i", "300,Al,B", "-Food", "200,Al"], 2),
+            (vec!["-i", "300,Al,B", "--json"], 2),
+            (vec!["-i", "300,Al,B", "--locale"], 2),
+            (vec!["-i", "300,Al,B", "--locale", "xx"], 3),
+        ] {
+            let error = parse_starting_receipt(&utils::strs_to_strings(args), Locale::default())
+                .unwrap_err();
+            assert_eq!(error.context().argument, Some(argument));
+        }
     }
 }
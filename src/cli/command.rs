@@ -0,0 +1,737 @@
+use crate::cli::arg_parser::{self, CliOptions};
+use crate::cli::store::{format_timestamp, SavedSplit, Store};
+use crate::cli::{diagnostic, receipt_file, records, repl, tokenizer, tui};
+use crate::core::locale::Locale;
+use crate::core::receipt::{ErrorContext, Receipt, SplittingError};
+use crate::core::summary::SummaryOptions;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+
+// A command, and what its help is generated from.
+struct Command {
+    name: &'static str,
+    // The ways of calling it, as the arguments that follow its name.
+    usage: &'static [&'static str],
+    summary: &'static str,
+    // What follows the usage in the command's own help.
+    details: &'static str,
+    // Takes the arguments from the command's name on.
+    run: fn(&[String]) -> Result<(), SplittingError>,
+}
+
+const RECEIPT_USAGE: &[&str] = &[
+    "Total,Person_1,Person_2[,...] -Item Value,Abbrev_1[,...] [...]",
+    "@Group Total[,Person,...] -Item Value,Abbrev_1[,...] [...]",
+    "--file Path [--output Format] [--write Path] [--save Name]",
+];
+
+// The first command is the default, run when the arguments start with a receipt.
+const COMMANDS: [Command; 4] = [
+    Command {
+        name: "split",
+        usage: RECEIPT_USAGE,
+        summary: "Split a receipt between the people sharing it",
+        details: "\
+Each item is a name prefixed with a dash (-) or a double dash (--), followed by its
+value and abbreviations of the people sharing it. An abbreviation matches a person
+whose name contains all of its characters, in the same order, and each abbreviation
+in a pattern must match a different person. Whatever is not itemized is split in
+proportion to the items.
+
+A group saved with the groups command shares a receipt as @ and its name, as in
+'300,@flatmates,Dave', or before the total, as in '@flatmates 300'. The aliases of
+the group then mean its people.
+
+Items are split equally unless an abbreviation is followed by its share:
+  Al:2      a weight, relative to the weights of the others sharing the item
+  Al:25%    a percentage of the item's value
+  Al=30     a fixed amount
+Fixed amounts and percentages are taken out first, and weights split the rest.
+
+Names with commas, colons or equals signs can be quoted, as in '300,\"Smith, J\",Bob',
+or have those characters escaped with a backslash, as in 'Smith\\, J'. After a lone
+'--', the remaining arguments are pairs of an item's name and its pattern, so that
+names may start with a dash: -- \"-20% promo\" -20,Al,B
+
+  --tax Amount[%]                       Add tax, split in proportion to the items
+  --tip Amount[%]                       Add a tip, split in proportion to the items
+  --service Amount[%]                   Add a service charge, split likewise
+  --prop Name=Amount[%][,Abbrev,...]    Add any other item split in proportion to the
+                                        items, optionally between some people only
+  --paid Abbrev,Amount[,Method]         Record a payment (by cash, card, transfer, ...)
+  --items Path                          Add the items of a file, or of standard input
+                                        if the path is -, as 'Name Value Abbrevs' lines
+                                        or CSV with a name,value,abbrevs header
+  --output Format                       Print the splits as a table (default), ascii,
+                                        markdown, csv, tsv or json
+  --layout Layout                       Lay tables out with people as columns (wide),
+                                        as rows (transposed), or as a card each
+                                        (cards). By default, the first that fits the
+                                        terminal is used (auto)
+  --color When                          Colour tables always, never, or on a terminal
+                                        unless NO_COLOR is set (auto, the default)
+  --compact                             Leave amounts of zero out of tables
+  --summary Style                       Print a message saying what everyone owes, and
+                                        who pays whom, to send to them instead of the
+                                        splits, as plain text or with emoji
+  --itemize                             List what each person owes for each item in
+                                        the summary
+  --explain Abbrev                      Also show how a person's share adds up, item
+                                        by item, with the cents rounding moved
+  --match Mode                          Match abbreviations exactly (default), ignoring
+                                        case (case-insensitive), by the start of names
+                                        (prefix), or forgiving typos (fuzzy)
+  --locale Locale                       Read and show amounts as in en (1,234.50, the
+                                        default), de (1.234,50), fr (1 234,50) or ch
+                                        (1'234.50). Patterns are then separated by ';'
+                                        wherever the decimal separator is a comma
+  --delimiter Char                      Separate the parts of patterns with another
+                                        character, such as '|'
+  --currency Symbol                     Show amounts with a currency, such as $ or €
+  --alias Alias=Person                  Let an alias always mean a person
+  --aliases Path                        Read aliases from a TOML file of
+                                        'Alias = \"Person\"' lines
+  --write Path                          Also write the splits to a file, in the format
+                                        its extension implies (.txt, .md, .csv, .json)
+  --save Name                           Save the split to the history under a name
+
+A receipt can also be read from a file with --file. A .toml or .json file has a
+'receipt' pattern, 'items' with a 'name' and a 'split' pattern, and optionally 'tax',
+'tip', 'service', 'proportional' items and 'paid' patterns. Any other file has the
+receipt's pattern on its first line, then an item's name followed by its pattern, or
+an option followed by its value, on each line. Lines starting with '#' are ignored.
+
+A percentage is of the itemized subtotal, before any of the proportional items.
+Any amount may be arithmetic, such as 3*4.50+2 or 120/2, with brackets and
+percentages (120*10% is 12).
+
+Example:
+  borrow-checker 300,Alice,Bob,Marshall --Food 200,Al,B,M --Wine 50,Al:2,B",
+        run: run_split,
+    },
+    Command {
+        name: "settle",
+        usage: RECEIPT_USAGE,
+        summary: "Work out who pays whom, from the payments recorded with --paid",
+        details: "\
+Takes the same arguments as split, but prints the payments that settle everyone up
+rather than the splits. Whoever owes the most pays whoever is owed the most, until
+everyone is settled, so no one makes more than one payment they need not.
+
+Example:
+  borrow-checker settle 300,Alice,Bob,Marshall --Food 300,Al,B,M --paid Al,250 --paid B,50",
+        run: run_settle,
+    },
+    Command {
+        name: "history",
+        usage: &["[list]", "reopen Number", "clear"],
+        summary: "List or reopen the splits saved with split --save",
+        details: "\
+Lists when each split was saved, its name and total, and what everyone owed. reopen
+splits the receipt of a saved split again, by its number in the list, and notes if
+the split has changed since, as a group or file it was made from might have. clear
+forgets them all.
+
+Saved splits are kept in the borrow-checker directory of the user's data directory
+(such as ~/.local/share on Linux), or in $BORROW_CHECKER_HOME if it is set.",
+        run: run_history,
+    },
+    Command {
+        name: "groups",
+        usage: &[
+            "[list]",
+            "add Name Person_1,Person_2[,...]",
+            "remove Name",
+            "alias Name Alias=Person",
+            "unalias Name Alias",
+        ],
+        summary: "Save the people of groups that often share receipts",
+        details: "\
+Lists the saved groups, adds a group, replacing the people of any of the same name,
+or removes one. A group's aliases always mean one of its people, wherever the group
+shares a receipt. Names are letters, digits, dashes and underscores.
+
+Groups are kept in the borrow-checker directory of the user's configuration
+directory (such as ~/.config on Linux), or in $BORROW_CHECKER_HOME if it is set.
+
+Example:
+  borrow-checker groups add flatmates Alice,Bob,Marshall
+  borrow-checker groups alias flatmates Ally=Alice
+  borrow-checker split @flatmates 300 --Food 200,Ally,B,M",
+        run: run_groups,
+    },
+];
+
+const OPTIONS: &str = "\
+Options:
+  -i, --interactive    Build the receipt one command at a time, with undo
+  -t, --tui            Build the receipt in a full-screen terminal UI, as in the web app
+  -h, --help           Print this help
+  -V, --version        Print the version
+  --json-errors        Print errors to stderr as a JSON object, for scripts
+
+Exit status:
+  0    The command succeeded
+  1    Something other than the input went wrong, such as the terminal
+  2    The arguments could not be understood
+  3    The people sharing the receipt are duplicated
+  4    Too few people share the receipt, or an item
+  5    The shares of an item cannot be split
+  6    An item's name, or a payment, is invalid
+  7    An abbreviation matches no one
+  8    An abbreviation matches more than one person
+  9    The items add up to more than the receipt's total
+  10   An amount is not a number
+  11   The payments do not add up to the receipt's total
+  12   A file could not be read or written
+  70   An internal error, which is a bug";
+
+fn help() -> String {
+    let commands: Vec<String> = COMMANDS
+        .iter()
+        .map(|command| format!("  {:<11}{}", command.name, command.summary))
+        .collect();
+    format!(
+        "\
+Split a receipt between the people sharing it.
+
+Usage: borrow-checker Command [Arguments]
+       borrow-checker {}
+       borrow-checker --interactive [Total,Person_1,Person_2[,...] [--locale ...]]
+       borrow-checker --tui [Total,Person_1,Person_2[,...] [--locale ...]]
+
+Commands:
+{}
+
+Without a command, the arguments are those of {}.
+
+{}
+
+Run 'borrow-checker Command --help' for the help of a command.",
+        COMMANDS[0].usage[0],
+        commands.join("\n"),
+        COMMANDS[0].name,
+        OPTIONS
+    )
+}
+
+fn command_help(command: &Command) -> String {
+    let usage: Vec<String> = command
+        .usage
+        .iter()
+        .map(|usage| format!("borrow-checker {} {}", command.name, usage))
+        .collect();
+    format!(
+        "{}.\n\nUsage: {}\n\n{}",
+        command.summary,
+        usage.join("\n       "),
+        command.details
+    )
+}
+
+// Shows errors as JSON on stderr, wherever it is given before a lone '--'.
+const JSON_ERRORS: &str = "--json-errors";
+
+pub fn run() -> ExitCode {
+    let mut args: Vec<String> = env::args().collect();
+    let json_errors = match args
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .position(|arg| arg == JSON_ERRORS)
+    {
+        Some(idx) => {
+            args.remove(idx);
+            true
+        }
+        None => false,
+    };
+
+    match args.get(1).map(|arg| arg.as_str()) {
+        None | Some("-h") | Some("--help") => {
+            println!("{}", help());
+            if args.len() < 2 {
+                ExitCode::from(2)
+            } else {
+                ExitCode::SUCCESS
+            }
+        }
+        Some("-V") | Some("--version") => {
+            println!("borrow-checker {}", env!("CARGO_PKG_VERSION"));
+            ExitCode::SUCCESS
+        }
+        Some("-i") | Some("--interactive") => start_interactive(&args[1..], json_errors, repl::run),
+        Some("-t") | Some("--tui") => start_interactive(&args[1..], json_errors, tui::run),
+        Some(name) => match COMMANDS.iter().find(|command| command.name == name) {
+            Some(command) => run_command(command, &args[1..], json_errors),
+            // Receipts always have a delimiter, so a lone word is a mistyped command.
+            None if name.chars().all(|c| c.is_ascii_alphabetic()) => {
+                let names: Vec<&str> = COMMANDS.iter().map(|command| command.name).collect();
+                let error = SplittingError::InvalidArgument(
+                    ErrorContext::new(format!("{} is not a command", name))
+                        .with_value(name)
+                        .with_help(format!(
+                            "use one of {}, or start with a receipt",
+                            names.join(", ")
+                        )),
+                );
+                report_error(&error, None, "borrow-checker", json_errors)
+            }
+            None => run_command(&COMMANDS[0], &args, json_errors),
+        },
+    }
+}
+
+// Print the error, as JSON if asked to, and exit with its code. Usage errors, as opposed
+// to errors in the receipt itself, are followed by where to find the usage.
+fn report_error(
+    error: &SplittingError,
+    input: Option<&str>,
+    usage: &str,
+    json_errors: bool,
+) -> ExitCode {
+    if json_errors {
+        eprintln!("{}", diagnostic::render_json_error(error, input));
+    } else {
+        eprintln!("{}", diagnostic::render_error(error, input));
+        if let SplittingError::InvalidArgument(_) = error {
+            eprintln!("\nRun '{} --help' for usage.", usage);
+        }
+    }
+    ExitCode::from(error.exit_code())
+}
+
+// Start an interactive mode, from the receipt after it if there is one.
+fn start_interactive(
+    args: &[String],
+    json_errors: bool,
+    run: fn(Option<Receipt>) -> ExitCode,
+) -> ExitCode {
+    match arg_parser::parse_starting_receipt(args, Locale::default()) {
+        Ok(receipt) => run(receipt),
+        Err(error) => {
+            let input = error
+                .context()
+                .argument
+                .and_then(|argument| args.get(argument));
+            report_error(
+                &error,
+                input.map(|arg| arg.as_str()),
+                "borrow-checker",
+                json_errors,
+            )
+        }
+    }
+}
+
+fn run_command(command: &Command, args: &[String], json_errors: bool) -> ExitCode {
+    if let Some("-h" | "--help") = args.get(1).map(|arg| arg.as_str()) {
+        println!("{}", command_help(command));
+        return ExitCode::SUCCESS;
+    }
+    match (command.run)(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            let context = error.context();
+            // The argument, or line of the file, that the error was found in.
+            let input = match (&context.file, context.argument) {
+                (Some(file), Some(line)) => {
+                    let source = match file.as_str() {
+                        records::STDIN => records::stdin_source().map(|source| source.to_string()),
+                        _ => fs::read_to_string(file).ok(),
+                    };
+                    source.and_then(|source| {
+                        source
+                            .lines()
+                            .nth(line.checked_sub(1)?)
+                            .map(|line| line.to_string())
+                    })
+                }
+                (None, Some(argument)) => args.get(argument).cloned(),
+                _ => None,
+            };
+            let usage = format!("borrow-checker {}", command.name);
+            report_error(&error, input.as_deref(), &usage, json_errors)
+        }
+    }
+}
+
+// The receipt of a command that splits it, which is checked like the web app checks it
+// before showing the splits, so that any problem is reported with its exit code.
+fn parse_receipt_args(args: &[String]) -> Result<(Receipt, CliOptions), SplittingError> {
+    let (receipt, options) = match args.get(1).map(|arg| arg.as_str()) {
+        Some("-f" | "--file") => parse_file_args(args)?,
+        _ => arg_parser::parse_args(args)?,
+    };
+    receipt.check_splittable()?;
+    Ok((receipt, options))
+}
+
+fn save_split(
+    receipt: &Receipt,
+    options: &CliOptions,
+    args: &[String],
+) -> Result<(), SplittingError> {
+    match &options.save {
+        Some(name) => Store::open()?.save_split(SavedSplit::new(name, &args[1..], receipt)?),
+        None => Ok(()),
+    }
+}
+
+fn run_split(args: &[String]) -> Result<(), SplittingError> {
+    let (mut receipt, options) = parse_receipt_args(args)?;
+    let explanation = match &options.explain {
+        Some(abbrev) => {
+            let person = receipt.parse_person(abbrev)?;
+            Some(receipt.explain(&person)?)
+        }
+        None => None,
+    };
+    match options.summary {
+        Some(style) => {
+            let summary = SummaryOptions {
+                style,
+                itemized: options.itemize,
+            };
+            println!("{}", receipt.summarize(summary, &receipt.locale)?);
+        }
+        None => receipt.display_splits(options.output, options.style)?,
+    }
+    if let Some(explanation) = explanation {
+        println!("\n{}'s share:", explanation.person);
+        for line in explanation.describe(&receipt.locale) {
+            println!("  {}", line);
+        }
+    }
+    if let Some(path) = &options.write {
+        receipt.write_splits(options.output, options.style, path)?;
+    }
+    save_split(&receipt, &options, args)
+}
+
+fn run_settle(args: &[String]) -> Result<(), SplittingError> {
+    let (receipt, options) = parse_receipt_args(args)?;
+    if receipt.payments.is_empty() {
+        return Err(SplittingError::InvalidArgument(
+            ErrorContext::new("No one has paid, so there is nothing to settle")
+                .with_help("record who paid with --paid Abbrev,Amount"),
+        ));
+    }
+    let transfers = receipt.settle()?;
+    if transfers.is_empty() {
+        println!("Everyone is settled up.");
+    }
+    for transfer in transfers {
+        println!(
+            "{} pays {} {}",
+            transfer.from,
+            transfer.to,
+            receipt.locale.format_money(transfer.amount)
+        );
+    }
+    save_split(&receipt, &options, args)
+}
+
+// The subcommand of a command such as history, which is the first of the given ones
+// when there is none.
+fn subcommand<'a>(args: &'a [String], subcommands: &[&'a str]) -> Result<&'a str, SplittingError> {
+    match args.get(1) {
+        None => Ok(subcommands[0]),
+        Some(arg) if subcommands.contains(&arg.as_str()) => Ok(arg),
+        Some(arg) => Err(SplittingError::InvalidArgument(
+            ErrorContext::new(format!("{} is not a subcommand of {}", arg, args[0]))
+                .with_value(arg)
+                .with_span(0, arg.len())
+                .with_help(format!("use one of {}", subcommands.join(", "))),
+        )
+        .in_argument(1)),
+    }
+}
+
+// Errors if there are more arguments than a subcommand takes.
+fn expect_arguments(args: &[String], count: usize) -> Result<(), SplittingError> {
+    if args.len() == count {
+        return Ok(());
+    }
+    let (argument, message) = match args.get(count) {
+        Some(extra) => (count, format!("{} does not take {}", args[1], extra)),
+        None => (args.len() - 1, format!("{} is missing arguments", args[1])),
+    };
+    Err(SplittingError::InvalidArgument(
+        ErrorContext::new(message).with_span(0, args[argument].len()),
+    )
+    .in_argument(argument))
+}
+
+fn run_history(args: &[String]) -> Result<(), SplittingError> {
+    let store = Store::open()?;
+    match subcommand(args, &["list", "reopen", "clear"])? {
+        "reopen" => {
+            expect_arguments(args, 3)?;
+            return reopen_split(&store, &args[2]);
+        }
+        "clear" => {
+            expect_arguments(args, 2)?;
+            return store.clear_history();
+        }
+        _ => expect_arguments(args, args.len().min(2))?,
+    }
+    let history = store.history()?;
+    if history.is_empty() {
+        println!("No splits have been saved yet. Save one with split --save Name.");
+    }
+    for (idx, split) in history.iter().enumerate() {
+        let locale = &split.locale;
+        let owed: Vec<String> = split
+            .people
+            .iter()
+            .zip(&split.owed)
+            .map(|(person, owed)| format!("{} {}", person, locale.format_money(*owed)))
+            .collect();
+        println!(
+            "{:>3}  {}  {}  {}  ({})",
+            idx + 1,
+            format_timestamp(split.saved_at),
+            split.name,
+            locale.format_money(split.total),
+            owed.join(", ")
+        );
+    }
+    Ok(())
+}
+
+// Split a saved split's receipt again, from the arguments it was saved with.
+fn reopen_split(store: &Store, number: &str) -> Result<(), SplittingError> {
+    let history = store.history()?;
+    let split = number
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| history.get(number.checked_sub(1)?))
+        .ok_or_else(|| {
+            SplittingError::InvalidArgument(
+                ErrorContext::new(format!("There is no saved split numbered {}", number))
+                    .with_span(0, number.len())
+                    .with_help(format!(
+                        "use the number of a split listed by 'borrow-checker history', from 1 to {}",
+                        history.len()
+                    )),
+            )
+            .in_argument(2)
+        })?;
+
+    let mut args = vec!["split".to_string()];
+    args.extend(split.args.iter().cloned());
+    // The saved arguments are not those of this command, so errors in them cannot point
+    // at where they are.
+    let (receipt, options) = parse_receipt_args(&args).map_err(|e| {
+        SplittingError::FileError(
+            ErrorContext::new(format!("{} can no longer be split: {}", split.name, e))
+                .with_value(args[1..].join(" ")),
+        )
+    })?;
+    println!("{}, saved {}", split.name, format_timestamp(split.saved_at));
+    receipt.display_splits(options.output, options.style)?;
+    if SavedSplit::new(&split.name, &split.args, &receipt)?.owed != split.owed {
+        eprintln!(
+            "note: the split has changed since it was saved, as a group or file it was made \
+            from has"
+        );
+    }
+    Ok(())
+}
+
+fn run_groups(args: &[String]) -> Result<(), SplittingError> {
+    let store = Store::open()?;
+    match subcommand(args, &["list", "add", "remove", "alias", "unalias"])? {
+        "add" => {
+            expect_arguments(args, 4)?;
+            let name = valid_group_name(args)?;
+            let people = tokenizer::split_fields(&args[3], ',')
+                .map_err(|e| e.in_argument(3))?
+                .into_iter()
+                .map(|field| field.text)
+                .collect::<Vec<_>>();
+            if let Some(idx) = people.iter().position(|person| person.trim().is_empty()) {
+                return Err(SplittingError::InvalidArgument(ErrorContext::new(format!(
+                    "Person {} of group {} has no name",
+                    idx + 1,
+                    name
+                )))
+                .in_argument(3));
+            }
+            store.save_group(name, people)
+        }
+        "remove" => {
+            expect_arguments(args, 3)?;
+            if store.remove_group(&args[2])? {
+                Ok(())
+            } else {
+                Err(SplittingError::InvalidArgument(
+                    ErrorContext::new(format!("There is no group called {}", args[2]))
+                        .with_span(0, args[2].len()),
+                )
+                .in_argument(2))
+            }
+        }
+        "alias" => {
+            expect_arguments(args, 4)?;
+            store
+                .update_aliases(&args[2], |aliases| {
+                    aliases.parse_insert(&args[3]).map_err(|e| e.in_argument(3))
+                })
+                .map_err(|e| e.with_span(0, args[2].len()).in_argument(2))
+        }
+        "unalias" => {
+            expect_arguments(args, 4)?;
+            store
+                .update_aliases(&args[2], |aliases| match aliases.remove(&args[3]) {
+                    Some(_) => Ok(()),
+                    None => Err(SplittingError::InvalidArgument(
+                        ErrorContext::new(format!("{} has no alias {}", args[2], args[3]))
+                            .with_span(0, args[3].len()),
+                    )
+                    .in_argument(3)),
+                })
+                .map_err(|e| e.with_span(0, args[2].len()).in_argument(2))
+        }
+        _ => {
+            expect_arguments(args, args.len().min(2))?;
+            let groups = store.groups()?;
+            if groups.is_empty() {
+                println!("No groups have been saved yet. Add one with groups add Name People.");
+            }
+            let width = groups
+                .keys()
+                .map(|name| name.chars().count())
+                .max()
+                .unwrap_or(0);
+            for (name, group) in groups {
+                let aliases: Vec<String> = group
+                    .aliases
+                    .iter()
+                    .map(|(alias, person)| format!("{}={}", alias, person))
+                    .collect();
+                let aliases = if aliases.is_empty() {
+                    String::new()
+                } else {
+                    format!("  (aliases {})", aliases.join(", "))
+                };
+                println!(
+                    "{:<width$}  {}{}",
+                    name,
+                    group.people.join(", "),
+                    aliases,
+                    width = width
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+// Group names are kept to what can be typed without quoting.
+fn valid_group_name(args: &[String]) -> Result<&str, SplittingError> {
+    let name = args[2].as_str();
+    if !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Ok(name);
+    }
+    Err(SplittingError::InvalidArgument(
+        ErrorContext::new(format!("{} cannot be the name of a group", name))
+            .with_span(0, name.len())
+            .with_help("use letters, digits, dashes and underscores, such as flatmates"),
+    )
+    .in_argument(2))
+}
+
+// Read the receipt from the file following --file. Only options about how to show the
+// splits may follow it, as everything else belongs in the file.
+fn parse_file_args(args: &[String]) -> Result<(Receipt, CliOptions), SplittingError> {
+    let path = args.get(2).ok_or_else(|| {
+        SplittingError::InvalidArgument(ErrorContext::new(
+            "--file must be followed by the path of a receipt file",
+        ))
+        .in_argument(1)
+    })?;
+    let (receipt, mut options) = receipt_file::load_receipt_file(Path::new(path))?;
+
+    let mut rest = args.iter().enumerate().skip(3);
+    while let Some((arg_idx, arg)) = rest.next() {
+        let option = arg.trim_start_matches('-');
+        let result = if !arg.starts_with('-') {
+            None
+        } else if arg_parser::FLAGS.contains(&option) {
+            options.parse_option(option, "")
+        } else {
+            rest.next()
+                .and_then(|(_, value)| options.parse_option(option, value))
+        };
+        result
+            .unwrap_or_else(|| {
+                Err(SplittingError::InvalidArgument(
+                    ErrorContext::new(format!(
+                        "Only options about showing or saving the splits, such as --output, \
+                        --summary, --write and --save, can follow a receipt file, but {} does",
+                        arg
+                    ))
+                    .with_value(arg)
+                    .with_span(0, arg.len()),
+                ))
+            })
+            .map_err(|e| e.in_argument(arg_idx))?;
+    }
+    Ok((receipt, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::command::{command_help, help, parse_receipt_args, COMMANDS};
+    use crate::core::receipt::SplittingError;
+    use crate::utils;
+
+    #[test]
+    fn help_is_generated_from_the_commands() {
+        let help = help();
+        for command in &COMMANDS {
+            assert!(help.contains(&format!("  {:<11}{}", command.name, command.summary)));
+        }
+        assert!(command_help(&COMMANDS[3]).starts_with(
+            "\
+Save the people of groups that often share receipts.
+
+Usage: borrow-checker groups [list]
+       borrow-checker groups add Name Person_1,Person_2[,...]
+       borrow-checker groups remove Name
+       borrow-checker groups alias Name Alias=Person
+       borrow-checker groups unalias Name Alias
+"
+        ));
+    }
+
+    #[test]
+    fn receipts_that_cannot_be_split_are_errors() {
+        let error =
+            |args: Vec<&str>| parse_receipt_args(&utils::strs_to_strings(args)).unwrap_err();
+
+        // Nothing to base the leftover on, with no items or only items of zero.
+        let empty = error(vec!["split", "300,Alice,Bob"]);
+        assert!(matches!(
+            empty,
+            SplittingError::InvalidShareConfiguration(_)
+        ));
+        assert_eq!(empty.exit_code(), 5);
+        let zero = error(vec!["split", "300,Alice,Bob", "--Food", "0,Al,B"]);
+        assert!(matches!(zero, SplittingError::InvalidFieldError(_)));
+        assert_eq!(zero.context().item_name.as_deref(), Some("Food"));
+        assert_eq!(zero.exit_code(), 6);
+        for tip in ["10", "18%"] {
+            let error = error(vec!["split", "300,Alice,Bob", "--tip", tip]);
+            assert_eq!(error.exit_code(), 5);
+            assert_eq!(error.context().argument, Some(3));
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use crate::core::receipt::SplittingError;
+use serde_json::{json, Value};
+
+// Errors are shown the way compilers show them, with the input they were found in and
+// the offending part of it underlined:
+//
+//     error: Bo does not match to a provided person name.
+//       --> argument 3
+//        |
+//      3 | 90,S,Bo
+//        |      ^^
+//        = help: did you mean Bob?
+//
+// The input is the argument, or the line of a file, that the error's span is within.
+// Without it, or a span, only the message, location and help are shown.
+pub fn render_error(error: &SplittingError, input: Option<&str>) -> String {
+    let context = error.context();
+    let mut lines = vec![format!("error: {}", error)];
+
+    let label = context.argument.map(|argument| argument.to_string());
+    let gutter = " ".repeat(label.as_ref().map_or(0, |label| label.len()));
+    match (&context.file, context.argument) {
+        (Some(file), Some(line)) => lines.push(format!("{} --> {}:{}", gutter, file, line)),
+        (Some(file), None) => lines.push(format!("{} --> {}", gutter, file)),
+        (None, Some(argument)) => lines.push(format!("{} --> argument {}", gutter, argument)),
+        (None, None) => {}
+    }
+
+    let underline = context.span.zip(input).and_then(|(span, input)| {
+        let column = input.get(..span.start)?.chars().count();
+        let width = input.get(span.start..span.end)?.chars().count().max(1);
+        Some((input, column, width))
+    });
+    if let (Some((input, column, width)), Some(label)) = (underline, &label) {
+        lines.push(format!("{} |", gutter));
+        lines.push(format!("{} | {}", label, input));
+        lines.push(format!(
+            "{} | {}{}",
+            gutter,
+            " ".repeat(column),
+            "^".repeat(width)
+        ));
+    }
+
+    if let Some(help) = &context.help {
+        match underline.and(label) {
+            Some(_) => lines.push(format!("{} = help: {}", gutter, help)),
+            None => lines.push(format!("help: {}", help)),
+        }
+    }
+    lines.join("\n")
+}
+
+// The error as a JSON object, for scripts to read rather than people. Fields that are
+// not known are left out, and the input is the argument, or line of a file, that the
+// error's span is within.
+pub fn render_json_error(error: &SplittingError, input: Option<&str>) -> String {
+    let context = error.context();
+    let mut fields = json!({
+        "code": error.code(),
+        "exit_code": error.exit_code(),
+        "message": context.message,
+        "item_index": context.item_idx,
+        "item_name": context.item_name,
+        "person": context.person,
+        "value": context.value,
+        "file": context.file,
+        // The line of the file, if there is one, or else the index of the argument.
+        "argument": context.argument,
+        "input": input,
+        "span": context.span.map(|span| json!({ "start": span.start, "end": span.end })),
+        "candidates": context.candidates,
+        "help": context.help,
+    });
+    if let Value::Object(fields) = &mut fields {
+        fields.retain(|_, value| !value.is_null() && *value != json!([]));
+    }
+    json!({ "error": fields }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::diagnostic::{render_error, render_json_error};
+    use crate::core::receipt::{ErrorContext, SplittingError};
+
+    #[test]
+    fn underline_the_offending_input() {
+        let error = SplittingError::InvalidAbbreviation(
+            ErrorContext::new("Bo does not match to a provided person name.")
+                .with_span(5, 7)
+                .with_help("did you mean Bob?"),
+        )
+        .in_argument(3);
+        assert_eq!(
+            render_error(&error, Some("90,S,Bo")),
+            "\
+error: Bo does not match to a provided person name.
+  --> argument 3
+  |
+3 | 90,S,Bo
+  |      ^^
+  = help: did you mean Bob?"
+        );
+
+        // Columns count characters rather than bytes, and empty spans get one caret.
+        let error = SplittingError::InvalidArgument(ErrorContext::new("Oops").with_span(5, 5))
+            .in_argument(12)
+            .in_file("dinner.txt");
+        assert_eq!(
+            render_error(&error, Some("Café 50,Al")),
+            "\
+error: Oops
+   --> dinner.txt:12
+   |
+12 | Café 50,Al
+   |     ^"
+        );
+
+        // Without the input, there is nothing to underline.
+        let error = SplittingError::InvalidArgument(
+            ErrorContext::new("Oops")
+                .with_span(0, 2)
+                .with_help("fix it"),
+        );
+        assert_eq!(render_error(&error, None), "error: Oops\nhelp: fix it");
+    }
+
+    #[test]
+    fn render_errors_as_json() {
+        let error = SplittingError::AmbiguousAbbreviation(
+            ErrorContext::new("S could be more than one person.")
+                .with_span(3, 4)
+                .with_candidates(vec!["Sam".into(), "Samuel".into()]),
+        )
+        .in_argument(2);
+        let json: serde_json::Value =
+            serde_json::from_str(&render_json_error(&error, Some("90,S,B"))).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "error": {
+                    "code": "ambiguous-abbreviation",
+                    "exit_code": 8,
+                    "message": "S could be more than one person.",
+                    "argument": 2,
+                    "input": "90,S,B",
+                    "span": { "start": 3, "end": 4 },
+                    "candidates": ["Sam", "Samuel"],
+                }
+            })
+        );
+    }
+}
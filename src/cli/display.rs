@@ -1,67 +1,613 @@
-use crate::core::receipt::{Receipt, SplittingError};
-use comfy_table::{Cell, Table, modifiers::UTF8_ROUND_CORNERS, presets::UTF8_FULL};
+use crate::core::receipt::{ErrorContext, Receipt, SplittingError};
+use comfy_table::{
+    modifiers::UTF8_ROUND_CORNERS,
+    presets::{ASCII_FULL, UTF8_FULL},
+    Cell, Table,
+};
+use rust_decimal::Decimal;
+use serde::{Serialize, Serializer};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    // UTF-8 table, coloured when printed to a terminal.
+    #[default]
+    Table,
+    Ascii,
+    Markdown,
+    Csv,
+    Tsv,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = SplittingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(Self::Table),
+            "ascii" => Ok(Self::Ascii),
+            "markdown" | "md" => Ok(Self::Markdown),
+            "csv" => Ok(Self::Csv),
+            "tsv" => Ok(Self::Tsv),
+            "json" => Ok(Self::Json),
+            _ => Err(SplittingError::InvalidArgument(
+                ErrorContext::new(format!(
+                    "{} is not an output format. Use one of table, ascii, markdown, csv, tsv or json.",
+                    s
+                ))
+                .with_value(s)
+                .with_span(0, s.len()),
+            )),
+        }
+    }
+}
+
+impl OutputFormat {
+    // The format a file should be written in, judging by its extension.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "md" | "markdown" => Some(Self::Markdown),
+            "csv" => Some(Self::Csv),
+            "tsv" => Some(Self::Tsv),
+            "json" => Some(Self::Json),
+            "txt" => Some(Self::Ascii),
+            _ => None,
+        }
+    }
+}
+
+// How a table is laid out: people as columns (wide), people as rows (transposed), or a
+// card of each person's share (cards). Auto picks the first of these that fits the
+// width of the terminal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Layout {
+    #[default]
+    Auto,
+    Wide,
+    Transposed,
+    Cards,
+}
+
+impl FromStr for Layout {
+    type Err = SplittingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "wide" => Ok(Self::Wide),
+            "transposed" => Ok(Self::Transposed),
+            "cards" => Ok(Self::Cards),
+            _ => Err(SplittingError::InvalidArgument(
+                ErrorContext::new(format!(
+                    "{} is not a layout. Use one of auto, wide, transposed or cards.",
+                    s
+                ))
+                .with_value(s)
+                .with_span(0, s.len()),
+            )),
+        }
+    }
+}
+
+// Whether tables are coloured. Auto colours them on a terminal, unless NO_COLOR is set.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = SplittingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(SplittingError::InvalidArgument(
+                ErrorContext::new(format!(
+                    "{} is not a colour choice. Use one of auto, always or never.",
+                    s
+                ))
+                .with_value(s)
+                .with_span(0, s.len()),
+            )),
+        }
+    }
+}
+
+// How the table and ascii formats are shown. Compact tables leave out amounts of zero.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TableStyle {
+    pub layout: Layout,
+    pub color: ColorChoice,
+    pub compact: bool,
+}
+
+// The width of the terminal the splits are printed to, if they are.
+fn terminal_width() -> Option<usize> {
+    terminal_size::terminal_size().map(|(width, _)| width.0 as usize)
+}
+
+// The width of a table as printed, in characters.
+fn table_width(table: &Table) -> usize {
+    let mut table = table.clone();
+    table.force_no_tty();
+    table
+        .to_string()
+        .lines()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+fn row_colour(kind: RowKind) -> Option<comfy_table::Color> {
+    match kind {
+        RowKind::Item => None,
+        RowKind::Total => Some(comfy_table::Color::Green),
+        RowKind::Net => Some(comfy_table::Color::Cyan),
+        RowKind::Leftover | RowKind::Paid => Some(comfy_table::Color::DarkGrey),
+    }
+}
+
+fn coloured_cell(text: String, colour: Option<comfy_table::Color>) -> Cell {
+    match colour {
+        Some(colour) => Cell::new(text).fg(colour),
+        None => Cell::new(text),
+    }
+}
+
+// A table with every column but the first aligned right, as amounts are.
+fn new_table(header: Vec<String>, rows: Vec<Vec<Cell>>) -> Table {
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS)
+        .set_header(header)
+        .add_rows(rows);
+    for (idx, column) in table.column_iter_mut().enumerate() {
+        if idx != 0 {
+            column.set_cell_alignment(comfy_table::CellAlignment::Right);
+        }
+    }
+    table
+}
+
+// What a row of the split stands for, so that the rows added by the calculation can be
+// told apart from items that happen to have the same name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RowKind {
+    Item,
+    Leftover,
+    Total,
+    Paid,
+    Net,
+}
+
+impl RowKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Item => "item",
+            Self::Leftover => "leftover",
+            Self::Total => "total",
+            Self::Paid => "paid",
+            Self::Net => "net",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SplitRow {
+    pub item: String,
+    pub kind: RowKind,
+    // In the order of Receipt.shared_by
+    #[serde(serialize_with = "serialize_amounts")]
+    pub splits: Vec<Decimal>,
+    #[serde(serialize_with = "serialize_amount")]
+    pub total: Decimal,
+}
+
+#[derive(Serialize)]
+struct JsonSplits<'a> {
+    people: &'a [String],
+    rows: &'a [SplitRow],
+}
+
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn escape_tsv(field: &str) -> String {
+    field.replace(['\t', '\n', '\r'], " ")
+}
+
+// Amounts in files meant for other programs are always to the cent, with a decimal
+// point and nothing else, whatever the locale.
+fn plain_amount(value: Decimal) -> String {
+    let mut value = value.round_dp(2);
+    value.rescale(2);
+    value.to_string()
+}
+
+// Amounts in JSON are strings written as in CSV, so that their scale does not depend on
+// how they were calculated.
+fn serialize_amount<S: Serializer>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&plain_amount(*value))
+}
+
+fn serialize_amounts<S: Serializer>(values: &[Decimal], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(values.iter().map(|value| plain_amount(*value)))
+}
+
+fn escape_markdown(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace('<', "\\<")
+        .replace('>', "\\>")
+}
 
 impl Receipt {
-    fn create_table(&self) -> Result<Table, SplittingError> {
+    // Every row of the split, from calculate_splits and calculate_payment_splits, along
+    // with what each of them stands for.
+    pub fn split_rows(&self) -> Result<Vec<SplitRow>, SplittingError> {
+        let (item_names, item_splits) = self.calculate_splits()?;
+        let (payment_names, payment_splits) = self.calculate_payment_splits()?;
+
+        // calculate_splits returns the items in order, then the leftover if there is
+        // any, and always ends with the total.
+        let total_idx = item_names.len() - 1;
+        let kinds = (0..item_names.len())
+            .map(|idx| {
+                if idx < self.items.len() {
+                    RowKind::Item
+                } else if idx < total_idx {
+                    RowKind::Leftover
+                } else {
+                    RowKind::Total
+                }
+            })
+            .chain(
+                [RowKind::Paid, RowKind::Net]
+                    .into_iter()
+                    .take(payment_names.len()),
+            );
+
+        Ok(item_names
+            .into_iter()
+            .chain(payment_names)
+            .zip(item_splits.into_iter().chain(payment_splits))
+            .zip(kinds)
+            .map(|((item_name, mut splits), kind)| {
+                let total = splits.pop().unwrap_or_default();
+                SplitRow {
+                    item: item_name.to_string(),
+                    kind,
+                    splits,
+                    total,
+                }
+            })
+            .collect())
+    }
+
+    fn header(&self) -> Vec<String> {
         let mut header = self.shared_by.clone();
         header.insert(0, "Item".into());
         header.push("Total".into());
+        header
+    }
 
-        let (item_names, item_splits) = self.calculate_splits()?;
+    // An amount in a table, which compact tables leave out if it is zero.
+    fn table_amount(&self, value: Decimal, compact: bool) -> String {
+        if compact && value.is_zero() {
+            String::new()
+        } else {
+            self.locale.format_money(value)
+        }
+    }
 
-        let rows: Vec<Vec<String>> = item_names
-            .into_iter()
-            .zip(item_splits.into_iter())
-            .map(|(item_name, splits)| {
-                let mut splits_as_str: Vec<String> = splits.iter().map(|x| x.to_string()).collect();
-                splits_as_str.insert(0, item_name.into());
-                splits_as_str
+    fn create_wide_table(&self, compact: bool) -> Result<Table, SplittingError> {
+        let rows = self
+            .split_rows()?
+            .iter()
+            .map(|row| {
+                let colour = row_colour(row.kind);
+                let mut cells = vec![coloured_cell(row.item.clone(), colour)];
+                cells.extend(
+                    row.splits
+                        .iter()
+                        .chain([&row.total])
+                        .map(|value| coloured_cell(self.table_amount(*value, compact), colour)),
+                );
+                cells
             })
             .collect();
+        Ok(new_table(self.header(), rows))
+    }
 
-        let mut table = Table::new();
-        table
-            .load_preset(UTF8_FULL)
-            .apply_modifier(UTF8_ROUND_CORNERS)
-            .set_header(header);
+    // People as rows, and the rows of the split as columns, with a row of their totals.
+    fn create_transposed_table(&self, compact: bool) -> Result<Table, SplittingError> {
+        let rows = self.split_rows()?;
+        let mut header = vec!["Person".to_string()];
+        header.extend(rows.iter().map(|row| row.item.clone()));
 
-        for (idx, column) in table.column_iter_mut().enumerate() {
-            if idx != 0 {
-                column.set_cell_alignment(comfy_table::CellAlignment::Right);
+        let mut table_rows: Vec<Vec<Cell>> = self
+            .shared_by
+            .iter()
+            .enumerate()
+            .map(|(person_idx, person)| {
+                let mut cells = vec![Cell::new(person)];
+                cells.extend(rows.iter().map(|row| {
+                    let value = self.table_amount(row.splits[person_idx], compact);
+                    coloured_cell(value, row_colour(row.kind))
+                }));
+                cells
+            })
+            .collect();
+        let mut totals = vec![Cell::new("Total")];
+        totals.extend(
+            rows.iter().map(|row| {
+                coloured_cell(self.table_amount(row.total, compact), row_colour(row.kind))
+            }),
+        );
+        table_rows.push(totals);
+        Ok(new_table(header, table_rows))
+    }
+
+    // A card of each person's share of every row of the split. Compact cards leave out
+    // the items a person does not share.
+    fn create_cards(&self, compact: bool) -> Result<Vec<Table>, SplittingError> {
+        let rows = self.split_rows()?;
+        Ok(self
+            .shared_by
+            .iter()
+            .enumerate()
+            .map(|(person_idx, person)| {
+                let cells = rows
+                    .iter()
+                    .filter(|row| {
+                        !compact
+                            || !row.splits[person_idx].is_zero()
+                            || !matches!(row.kind, RowKind::Item | RowKind::Leftover)
+                    })
+                    .map(|row| {
+                        let colour = row_colour(row.kind);
+                        vec![
+                            coloured_cell(row.item.clone(), colour),
+                            coloured_cell(self.locale.format_money(row.splits[person_idx]), colour),
+                        ]
+                    })
+                    .collect();
+                new_table(vec![person.clone(), "Share".to_string()], cells)
+            })
+            .collect())
+    }
+
+    // The tables of a layout, which is never Auto.
+    fn create_layout(&self, layout: Layout, compact: bool) -> Result<Vec<Table>, SplittingError> {
+        match layout {
+            Layout::Auto | Layout::Wide => Ok(vec![self.create_wide_table(compact)?]),
+            Layout::Transposed => Ok(vec![self.create_transposed_table(compact)?]),
+            Layout::Cards => self.create_cards(compact),
+        }
+    }
+
+    // The tables of the style's layout, or of the first layout that fits the width if it
+    // is Auto and there is one. Cards are as narrow as tables get.
+    fn create_tables(
+        &self,
+        style: TableStyle,
+        width: Option<usize>,
+    ) -> Result<Vec<Table>, SplittingError> {
+        match (style.layout, width) {
+            (Layout::Auto, Some(width)) => {
+                for layout in [Layout::Wide, Layout::Transposed] {
+                    let tables = self.create_layout(layout, style.compact)?;
+                    if tables.iter().all(|table| table_width(table) <= width) {
+                        return Ok(tables);
+                    }
+                }
+                self.create_cards(style.compact)
             }
+            (layout, _) => self.create_layout(layout, style.compact),
+        }
+    }
+
+    fn create_delimited(
+        &self,
+        delimiter: &str,
+        escape: fn(&str) -> String,
+    ) -> Result<String, SplittingError> {
+        let rows = self.split_rows()?;
+        let mut header = self.header();
+        header.push("Kind".into());
+
+        let mut lines: Vec<String> = vec![header
+            .iter()
+            .map(|x| escape(x))
+            .collect::<Vec<_>>()
+            .join(delimiter)];
+        for row in rows.iter() {
+            let mut fields: Vec<String> = vec![escape(&row.item)];
+            fields.extend(row.splits.iter().map(|x| plain_amount(*x)));
+            fields.push(plain_amount(row.total));
+            fields.push(row.kind.as_str().to_string());
+            lines.push(fields.join(delimiter));
         }
+        Ok(lines.join("\n") + "\n")
+    }
+
+    fn create_markdown(&self) -> Result<String, SplittingError> {
+        let rows = self.split_rows()?;
+        let header = self.header();
 
+        let mut lines: Vec<String> = vec![
+            format!(
+                "| {} |",
+                header
+                    .iter()
+                    .map(|x| escape_markdown(x))
+                    .collect::<Vec<_>>()
+                    .join(" | ")
+            ),
+            format!("|:---|{}", "---:|".repeat(header.len() - 1)),
+        ];
         for row in rows.iter() {
-            if row[0] == "<total>" || row[0] == "<leftover>" {
-                let fg_col = if row[0] == "<total>" {
-                    comfy_table::Color::Green
-                } else {
-                    comfy_table::Color::DarkGrey
-                };
+            let mut fields: Vec<String> = row
+                .splits
+                .iter()
+                .map(|x| escape_markdown(&self.locale.format_money(*x)))
+                .collect();
+            fields.push(escape_markdown(&self.locale.format_money(row.total)));
+            // Rows added by the calculation stand out from the items.
+            let (item, fields) = match row.kind {
+                RowKind::Item => (escape_markdown(&row.item), fields),
+                RowKind::Total => (
+                    format!("**{}**", escape_markdown(&row.item)),
+                    fields.iter().map(|x| format!("**{}**", x)).collect(),
+                ),
+                _ => (format!("_{}_", escape_markdown(&row.item)), fields),
+            };
+            lines.push(format!("| {} | {} |", item, fields.join(" | ")));
+        }
+        Ok(lines.join("\n") + "\n")
+    }
 
-                let row: Vec<Cell> = row.iter().map(|x| Cell::new(x).fg(fg_col)).collect();
-                table.add_row(row);
-            } else {
-                table.add_row(row);
+    pub fn render_splits(&self, format: OutputFormat) -> Result<String, SplittingError> {
+        self.render_styled_splits(format, TableStyle::default())
+    }
+
+    // Render the splits for the terminal, in the layout and colours of the style.
+    pub fn render_styled_splits(
+        &self,
+        format: OutputFormat,
+        style: TableStyle,
+    ) -> Result<String, SplittingError> {
+        let width = match style.layout {
+            Layout::Auto => terminal_width(),
+            _ => None,
+        };
+        self.render(format, style, true, width)
+    }
+
+    // Colours are only used when printing to a terminal, never in a file, and a terminal
+    // of a known width may call for a narrower layout.
+    fn render(
+        &self,
+        format: OutputFormat,
+        style: TableStyle,
+        terminal: bool,
+        width: Option<usize>,
+    ) -> Result<String, SplittingError> {
+        match format {
+            OutputFormat::Table => {
+                let mut tables = self.create_tables(style, width)?;
+                let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+                for table in tables.iter_mut() {
+                    match style.color {
+                        _ if !terminal => table.force_no_tty(),
+                        ColorChoice::Always => table.enforce_styling(),
+                        ColorChoice::Never => table.force_no_tty(),
+                        ColorChoice::Auto if no_color => table.force_no_tty(),
+                        ColorChoice::Auto => table,
+                    };
+                }
+                let tables: Vec<String> = tables.iter().map(|table| table.to_string()).collect();
+                Ok(format!("\n{}\n", tables.join("\n")))
+            }
+            OutputFormat::Ascii => {
+                let mut tables = self.create_tables(style, width)?;
+                for table in tables.iter_mut() {
+                    table.load_preset(ASCII_FULL).force_no_tty();
+                }
+                let tables: Vec<String> = tables.iter().map(|table| table.to_string()).collect();
+                Ok(format!("{}\n", tables.join("\n")))
+            }
+            OutputFormat::Markdown => self.create_markdown(),
+            OutputFormat::Csv => self.create_delimited(",", escape_csv),
+            OutputFormat::Tsv => self.create_delimited("\t", escape_tsv),
+            OutputFormat::Json => {
+                let rows = self.split_rows()?;
+                let splits = JsonSplits {
+                    people: &self.shared_by,
+                    rows: &rows,
+                };
+                serde_json::to_string_pretty(&splits)
+                    .map(|json| json + "\n")
+                    .map_err(|e| SplittingError::InternalError(e.to_string().into()))
             }
         }
-
-        Ok(table)
     }
 
-    pub fn display_splits(&self) -> Result<(), SplittingError> {
-        let table = self.create_table()?;
-        print!("\n{table}\n");
+    pub fn display_splits(
+        &self,
+        format: OutputFormat,
+        style: TableStyle,
+    ) -> Result<(), SplittingError> {
+        print!("{}", self.render_styled_splits(format, style)?);
         Ok(())
     }
+
+    // Write the splits to a file, in the format its extension implies, if any. Tables in
+    // files are wide unless another layout is asked for.
+    pub fn write_splits(
+        &self,
+        format: OutputFormat,
+        style: TableStyle,
+        path: &Path,
+    ) -> Result<(), SplittingError> {
+        let format = OutputFormat::from_extension(path).unwrap_or(format);
+        fs::write(path, self.render(format, style, false, None)?).map_err(|e| {
+            SplittingError::FileError(
+                ErrorContext::new(format!("Could not write to {}: {}", path.display(), e))
+                    .with_value(path.display()),
+            )
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::core::receipt::Receipt;
+    use crate::cli::display::{
+        table_width, ColorChoice, Layout, OutputFormat, RowKind, TableStyle,
+    };
+    use crate::core::receipt::{PaymentMethod, Receipt};
     use crate::utils;
+    use comfy_table::Table;
     use rust_decimal::prelude::*;
 
+    fn create_receipt() -> Receipt {
+        let mut receipt = Receipt::new(dec![300], vec!["Alice", "Bob", "Marshall"]).unwrap();
+        receipt
+            .add_item_split_by_ratio(
+                dec![200],
+                "Food".into(),
+                utils::strs_to_strings(vec!["Alice", "Marshall", "Bob"]),
+                None,
+            )
+            .unwrap();
+        receipt
+            .add_item_split_by_ratio(
+                dec![50],
+                "Drinks, Soft".into(),
+                utils::strs_to_strings(vec!["Alice", "Bob"]),
+                None,
+            )
+            .unwrap();
+        receipt
+    }
+
     #[test]
     fn test_create_table() {
         let mut receipt = Receipt::new(
@@ -91,23 +637,195 @@ mod tests {
             receipt.items
         );
 
-        let mut table = receipt.create_table().unwrap();
+        let mut table = receipt.create_wide_table(false).unwrap();
 
         table.force_no_tty();
 
         let expected = "
-╭────────────┬────────┬────────┬──────────┬───────╮
-│ Item       ┆  Alice ┆    Bob ┆ Marshall ┆ Total │
-╞════════════╪════════╪════════╪══════════╪═══════╡
-│ Food       ┆  66.67 ┆  66.67 ┆    66.67 ┆   200 │
-├╌╌╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌┤
-│ Drinks     ┆     25 ┆     25 ┆        0 ┆    50 │
-├╌╌╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌┤
-│ <leftover> ┆  18.33 ┆  18.33 ┆    13.33 ┆    50 │
-├╌╌╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌┤
-│ <total>    ┆ 110.00 ┆ 110.00 ┆    80.00 ┆   300 │
-╰────────────┴────────┴────────┴──────────┴───────╯";
+╭────────────┬────────┬────────┬──────────┬────────╮
+│ Item       ┆  Alice ┆    Bob ┆ Marshall ┆  Total │
+╞════════════╪════════╪════════╪══════════╪════════╡
+│ Food       ┆  66.67 ┆  66.67 ┆    66.67 ┆ 200.00 │
+├╌╌╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌┤
+│ Drinks     ┆  25.00 ┆  25.00 ┆     0.00 ┆  50.00 │
+├╌╌╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌┤
+│ <leftover> ┆  18.33 ┆  18.33 ┆    13.33 ┆  50.00 │
+├╌╌╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌╌╌┼╌╌╌╌╌╌╌╌┤
+│ <total>    ┆ 110.00 ┆ 110.00 ┆    80.00 ┆ 300.00 │
+╰────────────┴────────┴────────┴──────────┴────────╯";
         let actual = "\n".to_string() + &table.to_string();
         assert_eq!(expected, actual)
     }
+
+    #[test]
+    fn test_split_rows_kinds() {
+        let mut receipt = create_receipt();
+        receipt
+            .add_payment("Bob".into(), dec![300], PaymentMethod::Card)
+            .unwrap();
+        let kinds: Vec<RowKind> = receipt
+            .split_rows()
+            .unwrap()
+            .iter()
+            .map(|row| row.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                RowKind::Item,
+                RowKind::Item,
+                RowKind::Leftover,
+                RowKind::Total,
+                RowKind::Paid,
+                RowKind::Net
+            ]
+        );
+
+        // An item named like a calculated row is still an item.
+        let mut receipt = Receipt::new(dec![50], vec!["Alice", "Bob"]).unwrap();
+        receipt
+            .add_item_split_by_ratio(
+                dec![50],
+                "<total>".into(),
+                utils::strs_to_strings(vec!["Alice", "Bob"]),
+                None,
+            )
+            .unwrap();
+        assert_eq!(receipt.split_rows().unwrap()[0].kind, RowKind::Item);
+    }
+
+    #[test]
+    fn test_render_csv_and_tsv() {
+        let receipt = create_receipt();
+        let expected = "\
+Item,Alice,Bob,Marshall,Total,Kind
+Food,66.67,66.67,66.67,200.00,item
+\"Drinks, Soft\",25.00,25.00,0.00,50.00,item
+<leftover>,18.33,18.33,13.33,50.00,leftover
+<total>,110.00,110.00,80.00,300.00,total
+";
+        assert_eq!(receipt.render_splits(OutputFormat::Csv).unwrap(), expected);
+
+        let tsv = receipt.render_splits(OutputFormat::Tsv).unwrap();
+        assert_eq!(
+            tsv.lines().nth(2).unwrap(),
+            "Drinks, Soft\t25.00\t25.00\t0.00\t50.00\titem"
+        );
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let receipt = create_receipt();
+        let expected = "\
+| Item | Alice | Bob | Marshall | Total |
+|:---|---:|---:|---:|---:|
+| Food | 66.67 | 66.67 | 66.67 | 200.00 |
+| Drinks, Soft | 25.00 | 25.00 | 0.00 | 50.00 |
+| _\\<leftover\\>_ | 18.33 | 18.33 | 13.33 | 50.00 |
+| **\\<total\\>** | **110.00** | **110.00** | **80.00** | **300.00** |
+";
+        assert_eq!(
+            receipt.render_splits(OutputFormat::Markdown).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_render_in_a_locale() {
+        let mut receipt = create_receipt();
+        receipt.locale = "de".parse().unwrap();
+        receipt.locale.currency = Some("€".into());
+        let markdown = receipt.render_splits(OutputFormat::Markdown).unwrap();
+        assert_eq!(
+            markdown.lines().nth(5).unwrap(),
+            "| **\\<total\\>** | **110,00 €** | **110,00 €** | **80,00 €** | **300,00 €** |"
+        );
+
+        // Files for other programs are not localized.
+        let csv = receipt.render_splits(OutputFormat::Csv).unwrap();
+        assert_eq!(
+            csv.lines().nth(4).unwrap(),
+            "<total>,110.00,110.00,80.00,300.00,total"
+        );
+    }
+
+    #[test]
+    fn test_render_json() {
+        let receipt = create_receipt();
+        let json: serde_json::Value =
+            serde_json::from_str(&receipt.render_splits(OutputFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["people"][2], "Marshall");
+        assert_eq!(json["rows"][2]["item"], "<leftover>");
+        assert_eq!(json["rows"][2]["kind"], "leftover");
+        assert_eq!(json["rows"][3]["kind"], "total");
+        assert_eq!(json["rows"][3]["splits"][2], "80.00");
+        assert_eq!(json["rows"][3]["total"], "300.00");
+        // Every amount is to the cent, however it was calculated.
+        assert_eq!(json["rows"][0]["splits"][0], "66.67");
+        assert_eq!(json["rows"][1]["splits"][2], "0.00");
+    }
+
+    #[test]
+    fn test_layouts_fit_the_terminal() {
+        let mut receipt = Receipt::new(
+            dec![300],
+            vec![
+                "Alice", "Bob", "Marshall", "Ted", "Robin", "Lily", "Barney", "Ranjit",
+            ],
+        )
+        .unwrap();
+        receipt
+            .add_item_split_by_ratio(
+                dec![300],
+                "Food".into(),
+                utils::strs_to_strings(vec!["Alice", "Bob", "Marshall", "Ted"]),
+                None,
+            )
+            .unwrap();
+        let width = |tables: Vec<Table>| tables.iter().map(table_width).max().unwrap();
+        let auto = TableStyle::default();
+        let wide = width(receipt.create_tables(auto, None).unwrap());
+        assert_eq!(receipt.create_tables(auto, Some(wide)).unwrap().len(), 1);
+
+        // Transposed, eight people are rows rather than columns.
+        let transposed = receipt.create_tables(auto, Some(wide - 1)).unwrap();
+        assert_eq!(transposed.len(), 1);
+        assert_eq!(transposed[0].row_count(), 9);
+        // Narrower still, everyone gets a card.
+        let cards = receipt.create_tables(auto, Some(20)).unwrap();
+        assert_eq!(cards.len(), 8);
+
+        // Compact tables leave out the zeros of those not sharing the food.
+        let compact = TableStyle {
+            layout: Layout::Cards,
+            compact: true,
+            ..auto
+        };
+        let cards = receipt.create_tables(compact, None).unwrap();
+        assert_eq!(cards[0].row_count(), 2);
+        assert_eq!(cards[7].row_count(), 1);
+        let ascii = receipt
+            .render(
+                OutputFormat::Ascii,
+                TableStyle {
+                    layout: Layout::Wide,
+                    ..compact
+                },
+                false,
+                None,
+            )
+            .unwrap();
+        assert!(ascii.contains("| Food    | 75.00 | 75.00 |    75.00 | 75.00 |       |      |"));
+
+        assert_eq!("Transposed".parse::<Layout>().unwrap(), Layout::Transposed);
+        assert!("tall".parse::<Layout>().is_err());
+        assert_eq!("never".parse::<ColorChoice>().unwrap(), ColorChoice::Never);
+    }
+
+    #[test]
+    fn test_render_ascii_has_no_colour() {
+        let rendered = create_receipt().render_splits(OutputFormat::Ascii).unwrap();
+        assert!(rendered.is_ascii());
+        assert!(!rendered.contains('\x1b'));
+    }
 }
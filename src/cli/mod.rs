@@ -1,4 +1,12 @@
 pub mod arg_parser;
+pub mod command;
+pub mod diagnostic;
 pub mod display;
 pub mod pattern_parser;
+pub mod receipt_file;
+pub mod records;
+pub mod repl;
+pub mod store;
+pub mod tokenizer;
+pub mod tui;
 pub mod utils;
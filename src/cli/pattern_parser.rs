@@ -1,72 +1,404 @@
+use crate::cli::tokenizer::{self, unquote, Field};
 use crate::cli::utils as parse_utils;
-use crate::core::receipt::{Receipt, SplittingError};
+use crate::core::locale::Locale;
+use crate::core::receipt::{
+    ErrorContext, MatchMode, PaymentMethod, Person, Receipt, SplittingError,
+};
 use crate::utils;
-use rust_decimal::Decimal;
+use rust_decimal::{Decimal, RoundingStrategy};
 
 // Contains any pattern based parsing of inputs for the package.
 
+// How much of an item a person shares, following their abbreviation in an item pattern:
+// 'Al:2' is a weight, 'Al:25%' a percentage of the item's value, and 'Al=30' a fixed
+// amount. A bare 'Al' is a weight of 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Share {
+    Weight(Decimal),
+    Percentage(Decimal),
+    Amount(Decimal),
+}
+
+// Parse an abbreviation and its share, returning the abbreviation's range in the token.
+fn parse_share(token: &str, locale: &Locale) -> Result<(Field, Share), SplittingError> {
+    let (abbrev, share) = match tokenizer::split_once_raw(token, |c| c == '=' || c == ':')? {
+        Some((abbrev, '=', amount)) => {
+            let amount = parse_utils::parse_decimal(&unquote(amount), abbrev.len() + 1, locale)?;
+            (abbrev, Share::Amount(amount))
+        }
+        Some((abbrev, _, weight)) => {
+            let offset = abbrev.len() + 1;
+            let weight = unquote(weight);
+            match weight.strip_suffix("%") {
+                Some(percentage) => (
+                    abbrev,
+                    Share::Percentage(parse_utils::parse_decimal(percentage, offset, locale)?),
+                ),
+                None => (
+                    abbrev,
+                    Share::Weight(parse_utils::parse_decimal(&weight, offset, locale)?),
+                ),
+            }
+        }
+        None => (token, Share::Weight(Decimal::ONE)),
+    };
+
+    let is_valid = match share {
+        Share::Weight(x) | Share::Percentage(x) | Share::Amount(x) => x > Decimal::ZERO,
+    };
+    if !is_valid {
+        return Err(SplittingError::InvalidShareConfiguration(
+            ErrorContext::new(format!(
+                "The share of {} must be positive, but it is {}.",
+                unquote(abbrev),
+                &token[abbrev.len() + 1..]
+            ))
+            .with_value(token)
+            .with_span(abbrev.len() + 1, token.len()),
+        ));
+    }
+    Ok((Field::new(abbrev, 0), share))
+}
+
+// Turn the shares of an item into share ratios. Fixed amounts and percentages are taken
+// out of the item's value first, and whatever remains is split by the weights. If every
+// share is a weight of 1, the item is split equally, as if no ratio was provided.
+fn shares_to_ratio(
+    value: Decimal,
+    shares: &[(Field, Share)],
+) -> Result<Option<Vec<Decimal>>, SplittingError> {
+    let weights: Vec<Decimal> = shares
+        .iter()
+        .filter_map(|(_, share)| match share {
+            Share::Weight(weight) => Some(*weight),
+            _ => None,
+        })
+        .collect();
+
+    if weights.len() == shares.len() {
+        return Ok(if weights.iter().all(|&x| x == Decimal::ONE) {
+            None
+        } else {
+            Some(weights)
+        });
+    }
+
+    let allotted: Decimal = shares
+        .iter()
+        .map(|(_, share)| match share {
+            Share::Amount(amount) => *amount,
+            Share::Percentage(percentage) => value * percentage / Decimal::ONE_HUNDRED,
+            Share::Weight(_) => Decimal::ZERO,
+        })
+        .sum();
+    let remaining = value - allotted;
+    let weight_total: Decimal = weights.iter().sum();
+
+    let pattern_end = shares.last().map(|(abbrev, _)| abbrev.end).unwrap_or(0);
+    if (weights.is_empty() && !remaining.is_zero())
+        || (!weights.is_empty() && remaining <= Decimal::ZERO)
+    {
+        return Err(SplittingError::InvalidShareConfiguration(
+            ErrorContext::new(format!(
+                "The fixed amounts and percentages add up to {}, which leaves {} of the item's \
+                value {} to split{}.",
+                allotted,
+                remaining,
+                value,
+                if weights.is_empty() {
+                    ", but nobody to split it between"
+                } else {
+                    ""
+                }
+            ))
+            .with_value(allotted)
+            .with_span(0, pattern_end),
+        ));
+    }
+
+    Ok(Some(
+        shares
+            .iter()
+            .map(|(_, share)| match share {
+                Share::Amount(amount) => *amount,
+                Share::Percentage(percentage) => value * percentage / Decimal::ONE_HUNDRED,
+                Share::Weight(weight) => remaining * weight / weight_total,
+            })
+            .collect(),
+    ))
+}
+
 impl Receipt {
     pub fn parse_create_receipt(amount_shared_by: &str) -> Result<Receipt, SplittingError> {
-        let (total, shared_by) = parse_utils::split_by_comma(
+        Receipt::parse_create_receipt_in(amount_shared_by, Locale::default())
+    }
+
+    // Like parse_create_receipt, but with the pattern, and every one after it, written in
+    // the given locale.
+    pub fn parse_create_receipt_in(
+        amount_shared_by: &str,
+        locale: Locale,
+    ) -> Result<Receipt, SplittingError> {
+        let (total, shared_by) = parse_utils::split_by_delimiter(
             amount_shared_by,
-            "Input must have pattern 'Total,Person_1[,Person_2,...]', but you have not provided the starting comma.",
+            locale.delimiter,
+            "Total,Person_1[,Person_2,...]",
+        )?;
+        let people_offset = total.len() + 1;
+        let total = parse_utils::parse_decimal(&unquote(total), 0, &locale)?;
+        let shared_by = tokenizer::split_fields(shared_by, locale.delimiter)
+            .map_err(|e| e.shift_span(people_offset))?;
+        let mut receipt = Receipt::new(
+            total,
+            shared_by
+                .iter()
+                .map(|person| person.text.as_str())
+                .collect(),
         )?;
-        let total = total.parse()?;
-        let shared_by: Vec<&str> = shared_by.split(",").collect();
-        Receipt::new(total, shared_by)
+        receipt.locale = locale;
+        Ok(receipt)
+    }
+
+    // The people an abbreviation could stand for, by the index of their name. A person's
+    // full name is never taken to abbreviate anyone else, and an alias (or abbreviation
+    // used before on this receipt) adds the person it was used for.
+    fn person_candidates(&self, abbrev: &str) -> Vec<usize> {
+        let folded = self.fold_case(abbrev);
+        let people: Vec<String> = self
+            .shared_by
+            .iter()
+            .map(|name| self.fold_case(name))
+            .collect();
+        let matching_people = |is_match: &dyn Fn(&str) -> bool| -> Vec<usize> {
+            (0..people.len())
+                .filter(|&person_idx| is_match(&people[person_idx]))
+                .collect()
+        };
+
+        let full_names = matching_people(&|name| name == folded);
+        if !full_names.is_empty() {
+            return full_names;
+        }
+
+        let mut matches = match self.matching {
+            MatchMode::Prefix => matching_people(&|name| name.starts_with(&folded)),
+            _ => matching_people(&|name| utils::is_abbrev_match_to_string(&folded, name)),
+        };
+        if matches.is_empty() && self.matching == MatchMode::Fuzzy {
+            // Allow a typo in short abbreviations, and two in longer ones. Every person
+            // equally close is a candidate, so that ties are reported.
+            let max_typos = if folded.chars().count() <= 4 { 1 } else { 2 };
+            let distances: Vec<usize> = people
+                .iter()
+                .map(|name| utils::edit_distance(&folded, name))
+                .collect();
+            let closest = distances.iter().copied().min().unwrap_or(usize::MAX);
+            if closest <= max_typos {
+                matches = matching_people(&|name| utils::edit_distance(&folded, name) == closest);
+            }
+        }
+
+        if let Some(person_idx) = self.previous_match(abbrev) {
+            if !matches.contains(&person_idx) {
+                matches.push(person_idx);
+            }
+        }
+        matches
+    }
+
+    // Case is important - Don vs. don can be considered different people.
+    // Minimal disruption to user, less code to peruse. Unless asked otherwise.
+    fn fold_case(&self, text: &str) -> String {
+        match self.matching {
+            MatchMode::Exact => text.to_string(),
+            _ => text.to_lowercase(),
+        }
+    }
+
+    // How to fix an abbreviation that matches nobody: the person whose name starts most
+    // like it, if any are close, or else everyone it could stand for.
+    fn suggest_person(&self, abbrev: &str) -> String {
+        let folded = abbrev.to_lowercase();
+        let length = folded.chars().count();
+        let closest = self
+            .shared_by
+            .iter()
+            .map(|name| {
+                let start: String = name.to_lowercase().chars().take(length).collect();
+                (utils::edit_distance(&folded, &start), name)
+            })
+            .min_by_key(|&(distance, _)| distance);
+        match closest {
+            Some((_, name))
+                if self.matching == MatchMode::Exact
+                    && utils::is_abbrev_match_to_string(&folded, &name.to_lowercase()) =>
+            {
+                format!(
+                    "did you mean {}? Abbreviations match case unless --match case-insensitive \
+                    is given",
+                    name
+                )
+            }
+            Some((distance, name)) if distance < length => format!("did you mean {}?", name),
+            _ => format!("abbreviate one of {}", self.shared_by.join(", ")),
+        }
+    }
+
+    // The person an abbreviation is an alias of, or was used for before on this receipt.
+    fn previous_match(&self, abbrev: &str) -> Option<usize> {
+        let person = match self.mapped_abbreviations.get(abbrev) {
+            Some(person) => Some(person),
+            None => {
+                let folded = self.fold_case(abbrev);
+                self.mapped_abbreviations
+                    .iter()
+                    .find(|(mapped, _)| self.fold_case(mapped) == folded)
+                    .map(|(_, person)| person)
+            }
+        }?;
+        self.shared_by.iter().position(|name| name == person)
     }
 
     fn align_to_shared_by(&mut self, abbrevs: &str) -> Result<Vec<String>, SplittingError> {
-        let abbrevs: Vec<&str> = abbrevs.split(",").collect();
+        self.align_abbrevs_to_shared_by(&tokenizer::split_fields(abbrevs, self.locale.delimiter)?)
+    }
+
+    // Like align_to_shared_by, but with the byte range of each abbreviation within the
+    // pattern, for error reporting.
+    fn align_abbrevs_to_shared_by(
+        &mut self,
+        abbrevs: &[Field],
+    ) -> Result<Vec<String>, SplittingError> {
+        let abbrev_strs: Vec<&str> = abbrevs.iter().map(|abbrev| abbrev.text.as_str()).collect();
+        let pattern_end = abbrevs.last().map(|abbrev| abbrev.end).unwrap_or(0);
 
         utils::is_string_vec_unique(
-            &abbrevs,
-            SplittingError::InvalidAbbreviation(format!(
-                "The abbreviation string: {} has duplicates.",
-                abbrevs.join(",")
-            )),
+            &abbrev_strs,
+            SplittingError::InvalidAbbreviation(
+                ErrorContext::new(format!(
+                    "The abbreviation string: {} has duplicates.",
+                    abbrev_strs.join(",")
+                ))
+                .with_value(abbrev_strs.join(","))
+                .with_span(0, pattern_end),
+            ),
         )?;
 
-        let mut matched_names: Vec<String> = Vec::new();
-
-        // Case is important - Don vs. don can be considered different people.
-        // Minimal disruption to user, less code to peruse.
-        for abbrev in abbrevs {
-            // If the abbreviation is already mapped to an existing name:
-            if let Some(existing_name) = self.mapped_abbreviations.get(abbrev) {
-                // If it doesn't map to another one, add this as a mapped name.
-                if matched_names.contains(existing_name) {
-                    return Err(SplittingError::DuplicatePeopleError(format!(
-                        "{} maps to {}, which has already been specified once.",
-                        abbrev, existing_name
-                    )));
-                } else {
-                    matched_names.push(existing_name.clone());
-                }
-            } else {
-                // If the abbreviation is not mapped, try to find a map.
-                let mut found = false;
-
-                for name in &self.shared_by {
-                    if utils::is_abbrev_match_to_string(abbrev, name)
-                        & !matched_names.contains(name)
-                    {
-                        self.mapped_abbreviations
-                            .insert(abbrev.to_string(), name.clone());
-                        found = true;
-                        matched_names.push(name.clone());
-                        break;
-                    }
-                }
-
-                // Not finding a match is an error.
-                if !found {
-                    return Err(SplittingError::InvalidAbbreviation(format!(
+        let mut candidates: Vec<Vec<usize>> = Vec::new();
+        for field in abbrevs {
+            let abbrev = field.text.as_str();
+            if abbrev.is_empty() {
+                return Err(SplittingError::InvalidAbbreviation(
+                    ErrorContext::new("An abbreviation cannot be empty.")
+                        .with_span(field.start, field.end),
+                ));
+            }
+            let matches = self.person_candidates(abbrev);
+            if matches.is_empty() {
+                return Err(SplittingError::InvalidAbbreviation(
+                    ErrorContext::new(format!(
                         "{} does not match to a provided person name.",
                         abbrev
-                    )));
+                    ))
+                    .with_value(abbrev)
+                    .with_span(field.start, field.end)
+                    .with_help(self.suggest_person(abbrev)),
+                ));
+            }
+            candidates.push(matches);
+        }
+
+        // Every abbreviation must be matched to a different person, so some of them may
+        // only be resolved by looking at the others.
+        if utils::perfect_matching(&candidates).is_none() {
+            let idx = (1..=candidates.len())
+                .find(|&count| utils::perfect_matching(&candidates[..count]).is_none())
+                .unwrap()
+                - 1;
+            let field = &abbrevs[idx];
+            let abbrev = field.text.as_str();
+            let names: Vec<String> = candidates[idx]
+                .iter()
+                .map(|&person_idx| self.shared_by[person_idx].clone())
+                .collect();
+            return Err(SplittingError::InvalidAbbreviation(
+                ErrorContext::new(format!(
+                    "{} can only match {}, who {} already matched by the other abbreviations.",
+                    abbrev,
+                    names.join(" or "),
+                    if names.len() == 1 { "is" } else { "are" }
+                ))
+                .with_value(abbrev)
+                .with_span(field.start, field.end)
+                .with_candidates(names)
+                .with_help("give each person sharing the item an abbreviation of their own"),
+            ));
+        }
+
+        // Resolve whatever the other abbreviations leave only one person for. When that
+        // is not enough, an abbreviation already used for someone on this receipt keeps
+        // meaning them, if it still can. Anything left could match more than one person.
+        let feasible = |candidates: &[Vec<usize>], idx: usize| -> Vec<usize> {
+            candidates[idx]
+                .iter()
+                .copied()
+                .filter(|&person_idx| {
+                    let mut trial = candidates.to_vec();
+                    trial[idx] = vec![person_idx];
+                    utils::perfect_matching(&trial).is_some()
+                })
+                .collect()
+        };
+        let mut unresolved: Vec<usize> = (0..abbrevs.len()).collect();
+        while !unresolved.is_empty() {
+            let mut resolved_any = false;
+            for &idx in unresolved.iter() {
+                if let [person_idx] = feasible(&candidates, idx).as_slice() {
+                    candidates[idx] = vec![*person_idx];
+                    resolved_any = true;
+                }
+            }
+            if !resolved_any {
+                let previous = unresolved.iter().find_map(|&idx| {
+                    let feasible = feasible(&candidates, idx);
+                    self.previous_match(&abbrevs[idx].text)
+                        .filter(|person_idx| feasible.contains(person_idx))
+                        .map(|person_idx| (idx, person_idx))
+                });
+                match previous {
+                    Some((idx, person_idx)) => candidates[idx] = vec![person_idx],
+                    None => {
+                        let idx = unresolved[0];
+                        let field = &abbrevs[idx];
+                        let abbrev = field.text.as_str();
+                        let names: Vec<String> = feasible(&candidates, idx)
+                            .iter()
+                            .map(|&person_idx| self.shared_by[person_idx].clone())
+                            .collect();
+                        return Err(SplittingError::AmbiguousAbbreviation(
+                            ErrorContext::new(format!(
+                                "{} could be any of {}.",
+                                abbrev,
+                                names.join(", ")
+                            ))
+                            .with_value(abbrev)
+                            .with_span(field.start, field.end)
+                            .with_candidates(names)
+                            .with_help("use more of the name to tell them apart"),
+                        ));
+                    }
                 }
             }
+            unresolved.retain(|&idx| candidates[idx].len() > 1);
+        }
+
+        let matched_names: Vec<String> = candidates
+            .iter()
+            .map(|matches| self.shared_by[matches[0]].clone())
+            .collect();
+        for (abbrev, name) in abbrevs.iter().zip(matched_names.iter()) {
+            self.mapped_abbreviations
+                .insert(abbrev.text.clone(), name.clone());
         }
 
         Ok(matched_names)
@@ -77,17 +409,128 @@ impl Receipt {
         item_name: &str,
         item_pattern: &str,
     ) -> Result<(), SplittingError> {
-        let (value, abbrevs) = parse_utils::split_by_comma(
+        let item_idx = self.items.len();
+        let (value, abbrevs) = parse_utils::split_by_delimiter(
             item_pattern,
-            &format!(
-                "The first argument must have pattern 'Value,Person_1[,Person_2,...]', but you have {}",
-                item_pattern
+            self.locale.delimiter,
+            "Value,Person_1[,Person_2,...]",
+        )
+        .map_err(|e| e.with_item(item_idx, item_name))?;
+        let abbrevs_offset = value.len() + 1;
+        let value: Decimal = parse_utils::parse_decimal(&unquote(value), 0, &self.locale)
+            .map_err(|e| e.with_item(item_idx, item_name))?;
+
+        let shares: Vec<(Field, Share)> = tokenizer::split_raw(abbrevs, self.locale.delimiter)
+            .and_then(|tokens| {
+                tokens
+                    .into_iter()
+                    .map(|(start, token)| {
+                        let (abbrev, share) =
+                            parse_share(token, &self.locale).map_err(|e| e.shift_span(start))?;
+                        Ok((abbrev.shifted(start), share))
+                    })
+                    .collect()
+            })
+            .map_err(|e| e.shift_span(abbrevs_offset).with_item(item_idx, item_name))?;
+        let abbrevs: Vec<Field> = shares.iter().map(|(abbrev, _)| abbrev.clone()).collect();
+        let shared_by = self
+            .align_abbrevs_to_shared_by(&abbrevs)
+            .map_err(|e| e.shift_span(abbrevs_offset).with_item(item_idx, item_name))?;
+        let share_ratio = shares_to_ratio(value, &shares)
+            .map_err(|e| e.shift_span(abbrevs_offset).with_item(item_idx, item_name))?;
+        self.add_item_split_by_ratio(value, item_name.to_string(), shared_by, share_ratio)?;
+
+        Ok(())
+    }
+
+    // Parse 'Amount[%][,Person_1,...]' into an item split in proportion to the other
+    // items, such as tax or tip. A percentage is of the itemized subtotal (excluding other
+    // proportional items), and is shown in the item's name. Without any people, the item
+    // is shared by everyone.
+    pub fn parse_add_proportional_item(
+        &mut self,
+        item_name: &str,
+        item_pattern: &str,
+    ) -> Result<(), SplittingError> {
+        let item_idx = self.items.len();
+        let (amount, abbrevs) =
+            match tokenizer::split_once_raw(item_pattern, |c| c == self.locale.delimiter)
+                .map_err(|e| e.with_item(item_idx, item_name))?
+            {
+                Some((amount, _, abbrevs)) => (amount, Some(abbrevs)),
+                None => (item_pattern, None),
+            };
+        let raw_amount_len = amount.len();
+        let amount = unquote(amount);
+
+        let (value, item_name) = match amount.strip_suffix("%") {
+            Some(percentage) => {
+                let percentage = parse_utils::parse_decimal(percentage, 0, &self.locale)
+                    .map_err(|e| e.with_item(item_idx, item_name))?;
+                let subtotal: Decimal = self
+                    .items
+                    .iter()
+                    .filter(|x| !x.is_prop_dist)
+                    .map(|x| x.value)
+                    .sum();
+                let value = (subtotal * percentage / Decimal::ONE_HUNDRED)
+                    .round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero);
+                (value, format!("{} ({}%)", item_name, percentage))
+            }
+            None => (
+                parse_utils::parse_decimal(&amount, 0, &self.locale)
+                    .map_err(|e| e.with_item(item_idx, item_name))?,
+                item_name.to_string(),
             ),
+        };
+
+        let shared_by = match abbrevs {
+            Some(abbrevs) => self.align_to_shared_by(abbrevs).map_err(|e| {
+                e.shift_span(raw_amount_len + 1)
+                    .with_item(item_idx, &item_name)
+            })?,
+            None => self.shared_by.clone(),
+        };
+        self.add_item_split_by_proportion(value, item_name, shared_by)?;
+
+        Ok(())
+    }
+
+    // Match a single abbreviation to the person it stands for.
+    pub fn parse_person(&mut self, abbrev: &str) -> Result<Person, SplittingError> {
+        let abbrev = Field {
+            text: abbrev.to_string(),
+            start: 0,
+            end: abbrev.len(),
+        };
+        self.align_abbrevs_to_shared_by(&[abbrev])
+            .map(|mut people| people.remove(0))
+    }
+
+    pub fn parse_add_payment(&mut self, payment_pattern: &str) -> Result<(), SplittingError> {
+        let (abbrev, amount_method) = parse_utils::split_by_delimiter(
+            payment_pattern,
+            self.locale.delimiter,
+            "Person,Amount[,Method]",
         )?;
-        let value: Decimal = value.parse()?;
-        let shared_by = self.align_to_shared_by(&abbrevs)?;
-        // Todo: Add parsing of ratios specified in item names
-        self.add_item_split_by_ratio(value, item_name.to_string(), shared_by, None)?;
+        let amount_offset = abbrev.len() + 1;
+        let (amount, method) =
+            match tokenizer::split_once_raw(amount_method, |c| c == self.locale.delimiter)
+                .map_err(|e| e.shift_span(amount_offset))?
+            {
+                Some((amount, _, method)) => (
+                    amount,
+                    unquote(method).parse().map_err(|e: SplittingError| {
+                        let method_offset = amount_offset + amount.len() + 1;
+                        e.with_span(method_offset, payment_pattern.len())
+                    })?,
+                ),
+                None => (amount_method, PaymentMethod::Cash),
+            };
+        let amount: Decimal =
+            parse_utils::parse_decimal(&unquote(amount), amount_offset, &self.locale)?;
+        let payer = self.parse_person(&unquote(abbrev))?;
+        self.add_payment(payer, amount, method)?;
 
         Ok(())
     }
@@ -95,7 +538,8 @@ impl Receipt {
 
 #[cfg(test)]
 mod test {
-    use crate::core::receipt::{Receipt, SplittingError};
+    use crate::core::alias::AliasTable;
+    use crate::core::receipt::{InputSpan, MatchMode, PaymentMethod, Receipt, SplittingError};
     use rust_decimal::prelude::*;
 
     #[test]
@@ -182,10 +626,292 @@ mod test {
         assert_eq!(receipt.items[1].shared_by, vec!["Sam", "Alice"]);
 
         let val = receipt.parse_add_named_item("More Drinks", "10,S,Sa,Al");
-        let _ = format!("Sa maps to Sam, which has already been specified once.");
+        let _ = "Sa maps to Sam, which has already been specified once.".to_string();
         assert!(matches!(val, Err(SplittingError::InvalidAbbreviation(_))));
     }
 
+    #[test]
+    fn abbreviations_are_matched_together() {
+        // Greedily, S would take Samuel and leave nobody for Su.
+        let mut receipt = Receipt::parse_create_receipt("300,Samuel,Sara").unwrap();
+        let val = receipt.align_to_shared_by("S,Su").unwrap();
+        assert_eq!(val, vec!["Sara", "Samuel"]);
+
+        // Characters must appear in order.
+        let mut receipt = Receipt::parse_create_receipt("300,Hannah,Noah").unwrap();
+        assert_eq!(
+            receipt.align_to_shared_by("nH").unwrap_err().code(),
+            "invalid-abbreviation"
+        );
+
+        // A full name is never an abbreviation of someone else.
+        let mut receipt = Receipt::parse_create_receipt("300,Sam,Samuel").unwrap();
+        assert_eq!(receipt.align_to_shared_by("Sam").unwrap(), vec!["Sam"]);
+    }
+
+    #[test]
+    fn ambiguous_abbreviations_list_the_candidates() {
+        let mut receipt = Receipt::parse_create_receipt("300,Alice,Sam,Samuel").unwrap();
+        let error = receipt
+            .parse_add_named_item("Drinks", "90,Al,S")
+            .unwrap_err();
+        assert!(matches!(error, SplittingError::AmbiguousAbbreviation(_)));
+        assert_eq!(error.context().candidates, vec!["Sam", "Samuel"]);
+        assert_eq!(error.context().span, Some(InputSpan { start: 6, end: 7 }));
+
+        // Once S has been used for someone on the receipt, it keeps meaning them.
+        receipt.parse_add_named_item("Food", "90,S,Su").unwrap();
+        receipt.parse_add_named_item("Drinks", "90,Al,S").unwrap();
+        assert_eq!(receipt.items[1].shared_by, vec!["Alice", "Sam"]);
+        receipt.parse_add_named_item("Cake", "30,Sa,S").unwrap();
+        assert_eq!(receipt.items[2].shared_by, vec!["Samuel", "Sam"]);
+        // Unless the other abbreviations rule them out.
+        receipt.parse_add_named_item("Pie", "30,Sam,S").unwrap();
+        assert_eq!(receipt.items[3].shared_by, vec!["Sam", "Samuel"]);
+
+        let error = receipt.parse_add_named_item("Tea", "10,Su,Sl").unwrap_err();
+        // Sl only abbreviates Samuel, whom Su has taken.
+        assert!(matches!(error, SplittingError::InvalidAbbreviation(_)));
+        assert_eq!(error.context().candidates, vec!["Samuel"]);
+    }
+
+    #[test]
+    fn match_people_by_mode_and_alias() {
+        let mut receipt = Receipt::parse_create_receipt("300,Alice,Alan,Marshall").unwrap();
+        assert!(receipt.align_to_shared_by("mh").is_err());
+        receipt.matching = MatchMode::CaseInsensitive;
+        assert_eq!(receipt.align_to_shared_by("mh").unwrap(), vec!["Marshall"]);
+
+        receipt.matching = MatchMode::Prefix;
+        assert!(receipt.align_to_shared_by("ms").is_err());
+        assert_eq!(receipt.align_to_shared_by("mar").unwrap(), vec!["Marshall"]);
+
+        // Typos are only forgiven when nothing matches, and equally close people tie.
+        receipt.matching = MatchMode::Fuzzy;
+        assert_eq!(receipt.align_to_shared_by("Alcie").unwrap(), vec!["Alice"]);
+        assert_eq!(receipt.align_to_shared_by("Ala").unwrap(), vec!["Alan"]);
+        assert!(receipt.align_to_shared_by("Bob").is_err());
+        let mut tied = Receipt::parse_create_receipt("300,Dan,Don").unwrap();
+        tied.matching = MatchMode::Fuzzy;
+        let error = tied.align_to_shared_by("Din").unwrap_err();
+        assert!(matches!(error, SplittingError::AmbiguousAbbreviation(_)));
+        assert_eq!(error.context().candidates, vec!["Dan", "Don"]);
+
+        // An alias means its person, even when it would abbreviate someone else.
+        let mut aliases = AliasTable::new();
+        aliases.parse_insert("Ally=Alice").unwrap();
+        aliases.parse_insert("Al=Alan").unwrap();
+        receipt.matching = MatchMode::Exact;
+        receipt.seed_aliases(&aliases);
+        assert_eq!(
+            receipt.align_to_shared_by("Ally,Al").unwrap(),
+            vec!["Alice", "Alan"]
+        );
+        assert_eq!(receipt.align_to_shared_by("Al").unwrap(), vec!["Alan"]);
+    }
+
+    #[test]
+    fn quoted_names_and_abbreviations() {
+        let mut receipt = Receipt::parse_create_receipt(r#"300,"Smith, J",Bob\,\ Jr"#).unwrap();
+        assert_eq!(receipt.shared_by, vec!["Smith, J", "Bob, Jr"]);
+
+        receipt
+            .parse_add_named_item("Food", r#"200,"Smith, J":2,"B""#)
+            .unwrap();
+        assert_eq!(receipt.items[0].shared_by, vec!["Smith, J", "Bob, Jr"]);
+        assert_eq!(receipt.items[0].share_ratio, vec![dec![2], dec![1]]);
+        receipt
+            .parse_add_payment(r#""Smith, J",300,"Card""#)
+            .unwrap();
+        assert_eq!(receipt.payments[0].payer, "Smith, J");
+
+        // Spans cover the abbreviation as it was written, quotes included.
+        let error = receipt
+            .parse_add_named_item("Wine", r#"50,"Xy",B"#)
+            .unwrap_err();
+        assert_eq!(error.context().span, Some(InputSpan { start: 3, end: 7 }));
+        let error = receipt
+            .parse_add_named_item("Wine", r#"50,"B"#)
+            .unwrap_err();
+        assert!(matches!(error, SplittingError::InvalidArgument(_)));
+        assert_eq!(error.context().span, Some(InputSpan { start: 3, end: 5 }));
+    }
+
+    #[test]
+    fn errors_suggest_a_fix() {
+        let mut receipt = Receipt::parse_create_receipt("300,Alice,Sam,Marshall").unwrap();
+        let help = |error: SplittingError| error.context().help.clone().unwrap();
+
+        let error = receipt
+            .parse_add_named_item("Food", "200,Al,Sx")
+            .unwrap_err();
+        assert_eq!(help(error), "did you mean Sam?");
+        let error = receipt
+            .parse_add_named_item("Food", "200,Al,Xy")
+            .unwrap_err();
+        assert_eq!(help(error), "abbreviate one of Alice, Sam, Marshall");
+        let error = receipt
+            .parse_add_named_item("Food", "200,Al,ms")
+            .unwrap_err();
+        assert!(help(error).contains("--match case-insensitive"));
+
+        // A missing comma is a malformed pattern, not a malformed amount.
+        let error = receipt.parse_add_named_item("Food", "200").unwrap_err();
+        assert!(matches!(error, SplittingError::InvalidArgument(_)));
+        assert!(help(error).contains("Value,Person_1"));
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_input() {
+        let mut receipt = Receipt::parse_create_receipt("300,Alice,Sam,Marshall").unwrap();
+        receipt.parse_add_named_item("Caviar", "150,Al,S").unwrap();
+
+        let error = receipt
+            .parse_add_named_item("Drinks", "90,S,Bo")
+            .unwrap_err();
+        assert_eq!(error.code(), "invalid-abbreviation");
+        let context = error.context();
+        assert_eq!(context.item_idx, Some(1));
+        assert_eq!(context.item_name.as_deref(), Some("Drinks"));
+        assert_eq!(context.value.as_deref(), Some("Bo"));
+        assert_eq!(context.span, Some(InputSpan { start: 5, end: 7 }));
+
+        // Amounts point at the first character that cannot be part of one.
+        let error = Receipt::parse_create_receipt("3OO,Alice,Sam").unwrap_err();
+        assert_eq!(error.context().span, Some(InputSpan { start: 1, end: 2 }));
+
+        let receipt = Receipt::parse_create_receipt("3*100,Alice,Sam").unwrap();
+        assert_eq!(receipt.value, dec![300]);
+
+        let error = Receipt::parse_create_receipt("300,Alice,Sam,Alice").unwrap_err();
+        assert_eq!(error.context().person.as_deref(), Some("Alice"));
+    }
+
+    #[test]
+    fn parse_item_ratios() {
+        let mut receipt = Receipt::parse_create_receipt("300,Alice,Sam,Marshall").unwrap();
+        receipt.parse_add_named_item("Wine", "90,Al:2,S:1").unwrap();
+        assert_eq!(receipt.items[0].shared_by, vec!["Alice", "Sam"]);
+        assert_eq!(receipt.items[0].share_ratio, vec![dec![2], dec![1]]);
+
+        // Percentages and fixed amounts come out first, weights split the rest.
+        receipt
+            .parse_add_named_item("Pizza", "100,Al:50%,S=20,M")
+            .unwrap();
+        assert_eq!(
+            receipt.items[1].share_ratio,
+            vec![dec![50], dec![20], dec![30]]
+        );
+
+        receipt.parse_add_named_item("Cake", "30,Al,S,M").unwrap();
+        assert_eq!(
+            receipt.items[2].share_ratio,
+            vec![Decimal::ONE, Decimal::ONE, Decimal::ONE]
+        );
+
+        let (_, splits) = receipt.calculate_splits().unwrap();
+        assert_eq!(splits[0], vec![dec![60], dec![30], dec![0], dec![90]]);
+        assert_eq!(splits[1], vec![dec![50], dec![20], dec![30], dec![100]]);
+    }
+
+    #[test]
+    fn malformed_ratios_point_at_the_token() {
+        let mut receipt = Receipt::parse_create_receipt("300,Alice,Sam,Marshall").unwrap();
+
+        let error = receipt
+            .parse_add_named_item("Wine", "90,Al:2,S:x")
+            .unwrap_err();
+        assert!(matches!(error, SplittingError::DecimalParsingError(_)));
+        assert_eq!(error.context().span, Some(InputSpan { start: 10, end: 11 }));
+
+        let error = receipt
+            .parse_add_named_item("Wine", "90,Al:0,S")
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            SplittingError::InvalidShareConfiguration(_)
+        ));
+        assert_eq!(error.context().span, Some(InputSpan { start: 6, end: 7 }));
+
+        // A fixed amount must be positive too.
+        let error = receipt
+            .parse_add_named_item("Gift", "60,Al=-5,S")
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            SplittingError::InvalidShareConfiguration(_)
+        ));
+        assert_eq!(error.context().span, Some(InputSpan { start: 6, end: 8 }));
+
+        // Percentages that do not cover the whole item, with nobody to take the rest.
+        let error = receipt
+            .parse_add_named_item("Wine", "90,Al:50%,S:25%")
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            SplittingError::InvalidShareConfiguration(_)
+        ));
+        assert!(receipt.items.is_empty());
+    }
+
+    #[test]
+    fn parse_proportional_items() {
+        let mut receipt = Receipt::parse_create_receipt("300,Alice,Sam,Marshall").unwrap();
+        receipt.parse_add_named_item("Food", "200,Al,S,M").unwrap();
+        receipt.parse_add_named_item("Drinks", "50,Al,S").unwrap();
+        receipt.parse_add_proportional_item("Tax", "8.25%").unwrap();
+        receipt
+            .parse_add_proportional_item("Delivery", "5,Al,S")
+            .unwrap();
+
+        assert_eq!(receipt.items[2].name, "Tax (8.25%)");
+        assert_eq!(receipt.items[2].value, dec![20.63]);
+        assert_eq!(receipt.items[2].shared_by, vec!["Alice", "Sam", "Marshall"]);
+        assert!(receipt.items[2].is_prop_dist);
+        assert_eq!(receipt.items[3].shared_by, vec!["Alice", "Sam"]);
+
+        let (_, splits) = receipt.calculate_splits().unwrap();
+        assert_eq!(splits[3], vec![dec![2.5], dec![2.5], dec![0], dec![5]]);
+
+        let val = receipt.parse_add_proportional_item("Tip", "x%");
+        assert!(matches!(val, Err(SplittingError::DecimalParsingError(_))));
+
+        // There must be items of some value, shared by the item's people, to be in
+        // proportion to.
+        let mut receipt = Receipt::parse_create_receipt("300,Alice,Sam,Marshall").unwrap();
+        for pattern in ["18%", "10"] {
+            let error = receipt
+                .parse_add_proportional_item("Tip", pattern)
+                .unwrap_err();
+            assert!(matches!(
+                error,
+                SplittingError::InvalidShareConfiguration(_)
+            ));
+            assert!(error.context().help.as_ref().unwrap().contains("first"));
+        }
+        receipt.parse_add_named_item("Food", "0,Al,S").unwrap();
+        assert!(receipt.parse_add_proportional_item("Tip", "10").is_err());
+        receipt.parse_add_named_item("Drinks", "50,Al").unwrap();
+        assert!(receipt.parse_add_proportional_item("Tip", "10,M").is_err());
+        receipt.parse_add_proportional_item("Tip", "10").unwrap();
+        assert_eq!(receipt.items.len(), 3);
+    }
+
+    #[test]
+    fn parse_payments() {
+        let mut receipt = Receipt::parse_create_receipt("300,Alice,Sam,Marshall").unwrap();
+        receipt.parse_add_payment("Al,200,card").unwrap();
+        receipt.parse_add_payment("M,100").unwrap();
+        assert_eq!(receipt.payments[0].payer, "Alice");
+        assert_eq!(receipt.payments[0].method, PaymentMethod::Card);
+        assert_eq!(receipt.payments[1].payer, "Marshall");
+        assert_eq!(receipt.payments[1].amount, dec![100]);
+        assert_eq!(receipt.payments[1].method, PaymentMethod::Cash);
+
+        let val = receipt.parse_add_payment("S");
+        assert!(matches!(val, Err(SplittingError::InvalidArgument(_))));
+    }
+
     // #[test]
     // fn add_tip_and_tax() {
     //     let mut receipt = Receipt::parse_create_receipt("300,Alice,Sam,Marshall").unwrap();
@@ -0,0 +1,407 @@
+use crate::cli::arg_parser::{build_receipt, CliOptions, OptionEntry, FLAGS};
+use crate::cli::tokenizer;
+use crate::core::alias::AliasTable;
+use crate::core::receipt::{ErrorContext, Receipt, SplittingError};
+use serde::Deserialize;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+// Receipts can be read from files, so that long ones can be written once and kept
+// alongside other notes. A file ending in .toml or .json is a document with the same
+// patterns as the command line:
+//
+//     receipt = "300,Alice,Bob,Marshall"
+//     tax = "8.25%"
+//     paid = ["B,300,Card"]
+//
+//     [[items]]
+//     name = "Food"
+//     split = "200,Al,B,M"
+//
+// Any other file is read line by line. The first line is the receipt's total and the
+// people sharing it, and every other line is either an item's name followed by its
+// pattern, or an option as it would be given on the command line:
+//
+//     # Dinner at Luigi's
+//     300,Alice,Bob,Marshall
+//     Food 200,Al,B,M
+//     Red wine 50,Al:2,B
+//     --tax 8.25%
+//     --paid B,300,Card
+//
+// Blank lines, and lines starting with a '#', are ignored. An item's name may be quoted
+// to start with a dash or a '#', or to end with something that looks like a pattern:
+//
+//     "-20% promo" -20,Al,B
+
+// Byte offset of a part of a line, where the part is a slice of the line.
+fn offset_in(line: &str, part: &str) -> usize {
+    part.as_ptr() as usize - line.as_ptr() as usize
+}
+
+fn invalid_line(message: String, line_no: usize, line: &str) -> SplittingError {
+    let start = offset_in(line, line.trim_start());
+    SplittingError::InvalidArgument(
+        ErrorContext::new(message)
+            .with_value(line.trim())
+            .with_span(start, start + line.trim().len()),
+    )
+    .in_argument(line_no)
+}
+
+pub fn parse_receipt_lines(source: &str) -> Result<(Receipt, CliOptions), SplittingError> {
+    let mut receipt_pattern: Option<OptionEntry> = None;
+    let mut entries: Vec<OptionEntry> = Vec::new();
+
+    for (line_idx, line) in source.lines().enumerate() {
+        let line_no = line_idx + 1;
+        let content = line.trim();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+
+        if receipt_pattern.is_none() {
+            receipt_pattern = Some(
+                OptionEntry::new(Some(line_no), "", content).with_offset(offset_in(line, content)),
+            );
+        } else if let Some(option) = content.strip_prefix('-') {
+            let option = option.strip_prefix('-').unwrap_or(option);
+            match option.split_once(char::is_whitespace) {
+                Some((option, value)) => {
+                    let value = value.trim_start();
+                    entries.push(
+                        OptionEntry::new(Some(line_no), option, value)
+                            .with_offset(offset_in(line, value)),
+                    );
+                }
+                None if FLAGS.contains(&option) => {
+                    entries.push(OptionEntry::new(Some(line_no), option, ""));
+                }
+                None => {
+                    return Err(invalid_line(
+                        format!("{} must be followed by its value on the same line", content),
+                        line_no,
+                        line,
+                    ))
+                }
+            }
+        } else {
+            // Item names may contain spaces, but patterns cannot.
+            match tokenizer::split_name_and_pattern(content)
+                .map_err(|e| e.shift_span(offset_in(line, content)).in_argument(line_no))?
+            {
+                Some((name, _, pattern)) => entries.push(
+                    OptionEntry::new(Some(line_no), &name.text, pattern)
+                        .with_offset(offset_in(line, pattern))
+                        .for_item(),
+                ),
+                None => {
+                    return Err(invalid_line(
+                        format!(
+                            "Line {} must be an item's name followed by its pattern \
+                            'Value,Person_1[,Person_2,...]', but it is {}",
+                            line_no, content
+                        ),
+                        line_no,
+                        line,
+                    ))
+                }
+            }
+        }
+    }
+
+    match receipt_pattern {
+        Some(receipt_pattern) => build_receipt(&receipt_pattern, &entries, None),
+        None => Err(SplittingError::InvalidArgument(ErrorContext::new(
+            "The receipt file is empty. Its first line must be the receipt's total \
+            and the people sharing it, as in 'Total,Person_1,Person_2[,...]'",
+        ))),
+    }
+}
+
+// A value in a receipt document, which TOML knows the position of but JSON does not.
+trait DocumentValue {
+    fn text(&self) -> &str;
+    fn span(&self) -> Option<Range<usize>>;
+}
+
+impl DocumentValue for String {
+    fn text(&self) -> &str {
+        self
+    }
+
+    fn span(&self) -> Option<Range<usize>> {
+        None
+    }
+}
+
+impl DocumentValue for toml::Spanned<String> {
+    fn text(&self) -> &str {
+        self.get_ref()
+    }
+
+    fn span(&self) -> Option<Range<usize>> {
+        Some(toml::Spanned::span(self))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DocumentItem<S> {
+    name: String,
+    split: S,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ReceiptDocument<S> {
+    receipt: S,
+    #[serde(default = "Vec::new")]
+    items: Vec<DocumentItem<S>>,
+    tax: Option<S>,
+    tip: Option<S>,
+    service: Option<S>,
+    // Other items split in proportion to the items, like a delivery fee.
+    #[serde(default = "Vec::new")]
+    proportional: Vec<DocumentItem<S>>,
+    #[serde(default = "Vec::new")]
+    paid: Vec<S>,
+    output: Option<S>,
+    #[serde(rename = "match")]
+    matching: Option<S>,
+    locale: Option<S>,
+    delimiter: Option<S>,
+    currency: Option<S>,
+    // Nicknames of the people sharing the receipt, as 'Alias = "Person"'.
+    #[serde(default)]
+    aliases: AliasTable,
+}
+
+// The line a value starts on, and its offset within that line. The span of a string
+// includes its opening quote.
+fn locate_in_source(source: &str, value: &impl DocumentValue) -> (Option<usize>, usize) {
+    match value.span() {
+        Some(span) => {
+            let start = (span.start + 1).min(source.len());
+            let line_start = source[..start].rfind('\n').map_or(0, |idx| idx + 1);
+            (
+                Some(source[..start].matches('\n').count() + 1),
+                start - line_start,
+            )
+        }
+        None => (None, 0),
+    }
+}
+
+fn document_entry(
+    source: &str,
+    option: &str,
+    value: &impl DocumentValue,
+    prefix: &str,
+) -> OptionEntry {
+    let (location, offset) = locate_in_source(source, value);
+    // The value may be preceded by a prefix that is not in the source, which any span
+    // found within it must be shifted back by.
+    OptionEntry::new(location, option, &format!("{}{}", prefix, value.text()))
+        .with_offset(offset.saturating_sub(prefix.len()))
+}
+
+impl<S: DocumentValue> ReceiptDocument<S> {
+    fn build(&self, source: &str) -> Result<(Receipt, CliOptions), SplittingError> {
+        let receipt_pattern = document_entry(source, "", &self.receipt, "");
+        let mut entries: Vec<OptionEntry> = Vec::new();
+        for item in self.items.iter() {
+            entries.push(document_entry(source, &item.name, &item.split, "").for_item());
+        }
+        for payment in self.paid.iter() {
+            entries.push(document_entry(source, "paid", payment, ""));
+        }
+        for (option, value) in [
+            ("tax", &self.tax),
+            ("tip", &self.tip),
+            ("service", &self.service),
+        ] {
+            if let Some(value) = value {
+                entries.push(document_entry(source, option, value, ""));
+            }
+        }
+        for item in self.proportional.iter() {
+            entries.push(document_entry(
+                source,
+                "prop",
+                &item.split,
+                // The name ends at the '=', before the pattern is split by its delimiter.
+                &format!("{}=", tokenizer::escape(&item.name, ',')),
+            ));
+        }
+        if let Some(output) = &self.output {
+            entries.push(document_entry(source, "output", output, ""));
+        }
+        for (option, value) in [
+            ("match", &self.matching),
+            ("locale", &self.locale),
+            ("delimiter", &self.delimiter),
+            ("currency", &self.currency),
+        ] {
+            if let Some(value) = value {
+                entries.push(document_entry(source, option, value, ""));
+            }
+        }
+        for (alias, person) in self.aliases.iter() {
+            entries.push(OptionEntry::new(
+                None,
+                "alias",
+                &format!("{}={}", alias, person),
+            ));
+        }
+        build_receipt(&receipt_pattern, &entries, None)
+    }
+}
+
+fn toml_error(source: &str, error: toml::de::Error) -> SplittingError {
+    let line = error
+        .span()
+        .map(|span| source[..span.start].matches('\n').count() + 1);
+    let error = SplittingError::InvalidArgument(ErrorContext::new(error.message()));
+    match line {
+        Some(line) => error.in_argument(line),
+        None => error,
+    }
+}
+
+pub fn parse_receipt_toml(source: &str) -> Result<(Receipt, CliOptions), SplittingError> {
+    let document: ReceiptDocument<toml::Spanned<String>> =
+        toml::from_str(source).map_err(|e| toml_error(source, e))?;
+    document.build(source)
+}
+
+pub fn parse_receipt_json(source: &str) -> Result<(Receipt, CliOptions), SplittingError> {
+    let document: ReceiptDocument<String> = serde_json::from_str(source).map_err(|e| {
+        SplittingError::InvalidArgument(ErrorContext::new(e.to_string())).in_argument(e.line())
+    })?;
+    document.build(source)
+}
+
+// Read aliases from a TOML file of 'Alias = "Person"' lines.
+pub fn load_alias_file(path: &Path) -> Result<AliasTable, SplittingError> {
+    let file = path.display().to_string();
+    let source = fs::read_to_string(path).map_err(|e| {
+        SplittingError::FileError(
+            ErrorContext::new(format!("Could not read {}: {}", file, e)).with_value(&file),
+        )
+    })?;
+    toml::from_str(&source).map_err(|e| toml_error(&source, e).in_file(&file))
+}
+
+// Read a receipt from a file, in the format its extension implies. Errors record the
+// file, and the line within it where known.
+pub fn load_receipt_file(path: &Path) -> Result<(Receipt, CliOptions), SplittingError> {
+    let file = path.display().to_string();
+    let source = fs::read_to_string(path).map_err(|e| {
+        SplittingError::FileError(
+            ErrorContext::new(format!("Could not read {}: {}", file, e)).with_value(&file),
+        )
+    })?;
+    let extension = path.extension().and_then(|ext| ext.to_str());
+    match extension.map(|ext| ext.to_lowercase()).as_deref() {
+        Some("toml") => parse_receipt_toml(&source),
+        Some("json") => parse_receipt_json(&source),
+        _ => parse_receipt_lines(&source),
+    }
+    .map_err(|e| e.in_file(&file))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::display::OutputFormat;
+    use crate::cli::receipt_file::{parse_receipt_json, parse_receipt_lines, parse_receipt_toml};
+    use crate::core::error::InputSpan;
+    use crate::core::receipt::SplittingError;
+    use rust_decimal::prelude::*;
+
+    #[test]
+    fn parse_line_format() {
+        let source = "\
+# Dinner at Luigi's
+300,Alice,Bob,Marshall
+
+Food 200,Al,B,M
+  Red wine 50,Al:2,B
+\"-20% promo\" -20,Al,B
+--tax 10%
+-paid B,300,Card
+--output csv
+";
+        let (receipt, options) = parse_receipt_lines(source).unwrap();
+        assert_eq!(receipt.value, dec![300]);
+        assert_eq!(receipt.items.len(), 4);
+        assert_eq!(receipt.items[1].name, "Red wine");
+        assert_eq!(receipt.items[2].name, "-20% promo");
+        assert_eq!(receipt.items[3].name, "Tax (10%)");
+        assert_eq!(receipt.payments[0].payer, "Bob");
+        assert_eq!(options.output, OutputFormat::Csv);
+    }
+
+    #[test]
+    fn line_format_errors_point_at_the_line() {
+        let source = "300,Alice,Bob\nFood 200,Al,B\n  Wine 50,Al,Xy\n";
+        let error = parse_receipt_lines(source).unwrap_err();
+        assert!(matches!(error, SplittingError::InvalidAbbreviation(_)));
+        assert_eq!(error.context().argument, Some(3));
+        // Relative to the line, including its indentation.
+        assert_eq!(error.context().span, Some(InputSpan { start: 13, end: 15 }));
+
+        let error = parse_receipt_lines("300,Alice,Bob\n# Comment\nFood\n").unwrap_err();
+        assert!(matches!(error, SplittingError::InvalidArgument(_)));
+        assert_eq!(error.context().argument, Some(3));
+
+        let error = parse_receipt_lines("# Nothing here\n\n").unwrap_err();
+        assert!(matches!(error, SplittingError::InvalidArgument(_)));
+    }
+
+    #[test]
+    fn parse_toml_format() {
+        let source = r#"
+receipt = "300,Alice,Bob,Marshall"
+tax = "10%"
+paid = ["B,300,Card"]
+
+[[items]]
+name = "Food"
+split = "200,Al,B,M"
+
+[[items]]
+name = "Wine"
+split = "50,Al,Xy"
+"#;
+        let error = parse_receipt_toml(source).unwrap_err();
+        assert!(matches!(error, SplittingError::InvalidAbbreviation(_)));
+        assert_eq!(error.context().argument, Some(12));
+        assert_eq!(error.context().span, Some(InputSpan { start: 15, end: 17 }));
+
+        let (receipt, _) = parse_receipt_toml(&source.replace("Xy", "B")).unwrap();
+        assert_eq!(receipt.items.len(), 3);
+        assert_eq!(receipt.items[2].name, "Tax (10%)");
+
+        let error = parse_receipt_toml("receipt = \"300,Alice,Bob\"\nitem = []\n").unwrap_err();
+        assert!(matches!(error, SplittingError::InvalidArgument(_)));
+        assert_eq!(error.context().argument, Some(2));
+    }
+
+    #[test]
+    fn parse_json_format() {
+        let source = r#"{
+            "receipt": "300,Alice,Bob",
+            "items": [{"name": "Food", "split": "200,Al,B"}],
+            "proportional": [{"name": "Delivery", "split": "5,B"}],
+            "paid": ["Al,300"]
+        }"#;
+        let (receipt, _) = parse_receipt_json(source).unwrap();
+        assert_eq!(receipt.items[1].name, "Delivery");
+        assert_eq!(receipt.items[1].shared_by, vec!["Bob"]);
+
+        let error = parse_receipt_json("{\n\"receipt\": 300\n}").unwrap_err();
+        assert_eq!(error.context().argument, Some(2));
+    }
+}
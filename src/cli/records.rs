@@ -0,0 +1,395 @@
+use crate::cli::tokenizer;
+use crate::core::expression;
+use crate::core::locale::Locale;
+use crate::core::receipt::{ErrorContext, Receipt, SplittingError};
+use std::fs;
+use std::io::{self, Read};
+use std::sync::OnceLock;
+
+// Items can be read in bulk with --items, from a file or from standard input, so that
+// they can be generated by other tools. Each line is a record of an item's name, value
+// and the abbreviations of the people sharing it:
+//
+//     Food 200 Al,B,M
+//     Red wine 50 Al:2,B
+//
+// A name followed by its pattern, as in a receipt file, is read as well. Alternatively,
+// the records are CSV, separated by commas, semicolons or tabs, with a header naming
+// the columns:
+//
+//     name,value,abbrevs
+//     Food,200,"Al,B,M"
+//     "Wine, red",50,"Al:2,B"
+//
+// Blank lines, and lines starting with a '#', are ignored. Errors name the line of the
+// record they were found in.
+
+// The name standard input is reported by.
+pub const STDIN: &str = "<stdin>";
+
+static STDIN_SOURCE: OnceLock<String> = OnceLock::new();
+
+// What was read from standard input, if anything was, to show the lines of errors in.
+pub fn stdin_source() -> Option<&'static str> {
+    STDIN_SOURCE.get().map(|source| source.as_str())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ItemRecord {
+    line_no: usize,
+    name: String,
+    pattern: String,
+    // Byte offset of the pattern within the line.
+    offset: usize,
+}
+
+const NAME_COLUMNS: [&str; 2] = ["name", "item"];
+const VALUE_COLUMNS: [&str; 3] = ["value", "amount", "price"];
+const ABBREVS_COLUMNS: [&str; 5] = ["abbrevs", "abbreviations", "people", "shared by", "split"];
+
+fn invalid_record(message: String, line_no: usize) -> SplittingError {
+    SplittingError::InvalidArgument(ErrorContext::new(message)).in_argument(line_no)
+}
+
+// The pattern of a record's value and abbreviations, each with its byte offset in the
+// line. Where the value comes first, the pattern is padded so that its spans are those
+// of the line.
+fn record_pattern(
+    value: (usize, &str),
+    abbrevs: (usize, &str),
+    delimiter: char,
+) -> (String, usize) {
+    // A value such as 1,234.50 is quoted where the comma is also the delimiter.
+    let (value, offset) = if value.1.contains(delimiter) {
+        (format!("\"{}\"", value.1), value.0.saturating_sub(1))
+    } else {
+        (value.1.to_string(), value.0)
+    };
+    let gap = abbrevs
+        .0
+        .saturating_sub(offset + value.len() + delimiter.len_utf8());
+    (
+        format!("{}{}{}{}", value, " ".repeat(gap), delimiter, abbrevs.1),
+        offset,
+    )
+}
+
+// The fields of a line of CSV, with the byte offset of each. Quoted fields may contain
+// the separator, and "" for a quote.
+fn csv_fields(line: &str, separator: char) -> Result<Vec<(usize, String)>, SplittingError> {
+    let mut fields: Vec<(usize, String)> = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    loop {
+        let start = chars.peek().map_or(line.len(), |&(idx, _)| idx);
+        let mut text = String::new();
+        if let Some(&(quote, '"')) = chars.peek() {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some((_, '"')) if chars.peek().map(|&(_, c)| c) == Some('"') => {
+                        chars.next();
+                        text.push('"');
+                    }
+                    Some((_, '"')) => break,
+                    Some((_, c)) => text.push(c),
+                    None => {
+                        return Err(SplittingError::InvalidArgument(
+                            ErrorContext::new("The quote is never closed")
+                                .with_span(quote, quote + 1)
+                                .with_help("close the quote, and write \"\" for a quote within it"),
+                        ))
+                    }
+                }
+            }
+            fields.push((start + 1, text));
+            // Anything between the closing quote and the separator is ignored.
+            for (_, c) in chars.by_ref() {
+                if c == separator {
+                    break;
+                }
+            }
+            if chars.peek().is_none() && !line.ends_with(separator) {
+                return Ok(fields);
+            }
+        } else {
+            let mut ended = true;
+            for (_, c) in chars.by_ref() {
+                if c == separator {
+                    ended = false;
+                    break;
+                }
+                text.push(c);
+            }
+            fields.push((start, text));
+            if ended {
+                return Ok(fields);
+            }
+        }
+    }
+}
+
+// The index of the first column of the header with one of the given names.
+fn find_column(header: &[(usize, String)], names: &[&str]) -> Option<usize> {
+    header
+        .iter()
+        .position(|(_, column)| names.contains(&column.trim().to_lowercase().as_str()))
+}
+
+fn parse_csv_records(
+    lines: &[(usize, &str)],
+    separator: char,
+    delimiter: char,
+) -> Result<Vec<ItemRecord>, SplittingError> {
+    let (header_no, header) = lines[0];
+    let header = csv_fields(header, separator).map_err(|e| e.in_argument(header_no))?;
+    let columns = (
+        find_column(&header, &NAME_COLUMNS),
+        find_column(&header, &VALUE_COLUMNS),
+        find_column(&header, &ABBREVS_COLUMNS),
+    );
+    let (name, value, abbrevs) = match columns {
+        (Some(name), Some(value), Some(abbrevs)) => (name, value, abbrevs),
+        _ => {
+            return Err(SplittingError::InvalidArgument(
+                ErrorContext::new("The header must name the name, value and abbrevs columns")
+                    .with_help("start with a header such as 'name,value,abbrevs'"),
+            )
+            .in_argument(header_no))
+        }
+    };
+
+    let mut records: Vec<ItemRecord> = Vec::new();
+    for &(line_no, line) in &lines[1..] {
+        let fields = csv_fields(line, separator).map_err(|e| e.in_argument(line_no))?;
+        let field = |column: usize| {
+            fields
+                .get(column)
+                .map(|(start, text)| (*start, text.as_str()))
+                .ok_or_else(|| {
+                    invalid_record(
+                        format!(
+                            "Line {} has {} columns, but the header has {}",
+                            line_no,
+                            fields.len(),
+                            header.len()
+                        ),
+                        line_no,
+                    )
+                })
+        };
+        let (pattern, offset) = record_pattern(field(value)?, field(abbrevs)?, delimiter);
+        records.push(ItemRecord {
+            line_no,
+            name: field(name)?.1.trim().to_string(),
+            pattern,
+            offset,
+        });
+    }
+    Ok(records)
+}
+
+fn parse_line_record(
+    line_no: usize,
+    line: &str,
+    locale: &Locale,
+) -> Result<ItemRecord, SplittingError> {
+    let start = line.len() - line.trim_start().len();
+    let content = line.trim();
+    let split = |text| {
+        tokenizer::split_name_and_pattern(text)
+            .map_err(|e| e.shift_span(start).in_argument(line_no))
+    };
+    let (name, pattern_offset, pattern) = split(content)?.ok_or_else(|| {
+        invalid_record(
+            format!(
+                "Line {} must be an item's name, value and abbreviations, as in \
+                'Food 200 Al,B', but it is {}",
+                line_no, content
+            ),
+            line_no,
+        )
+        .with_help("CSV needs a header naming its columns, such as 'name,value,abbrevs'")
+    })?;
+    // The last two parts are the value and the abbreviations, unless the last starts
+    // with an amount, in which case it is the whole pattern.
+    let first_field = pattern.split(locale.delimiter).next().unwrap_or_default();
+    let is_pattern = expression::evaluate_in(first_field, locale).is_ok();
+    let record = match split(content[..pattern_offset].trim_end())? {
+        Some((value_name, value_offset, value)) if !is_pattern => {
+            let (pattern, offset) = record_pattern(
+                (start + value_offset, value),
+                (start + pattern_offset, pattern),
+                locale.delimiter,
+            );
+            ItemRecord {
+                line_no,
+                name: value_name.text,
+                pattern,
+                offset,
+            }
+        }
+        _ => ItemRecord {
+            line_no,
+            name: name.text,
+            pattern: pattern.to_string(),
+            offset: start + pattern_offset,
+        },
+    };
+    Ok(record)
+}
+
+// The records of a source, as lines of 'Name Value Abbrevs' or as CSV with a header.
+fn parse_item_records(source: &str, locale: &Locale) -> Result<Vec<ItemRecord>, SplittingError> {
+    let lines: Vec<(usize, &str)> = source
+        .lines()
+        .enumerate()
+        .map(|(line_idx, line)| (line_idx + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .collect();
+    let header = lines.first().map_or("", |(_, line)| line);
+    for separator in [',', ';', '\t'] {
+        let is_header = csv_fields(header, separator)
+            .is_ok_and(|fields| find_column(&fields, &NAME_COLUMNS).is_some());
+        if is_header {
+            return parse_csv_records(&lines, separator, locale.delimiter);
+        }
+    }
+    lines
+        .into_iter()
+        .map(|(line_no, line)| parse_line_record(line_no, line, locale))
+        .collect()
+}
+
+// Add the items of a file of records, or of standard input if the path is '-'. Errors
+// are in the file, at the line of the record. Standard input can only be read once.
+pub fn add_item_records(receipt: &mut Receipt, path: &str) -> Result<(), SplittingError> {
+    let (file, source) = if path == "-" {
+        if STDIN_SOURCE.get().is_some() {
+            return Err(SplittingError::InvalidArgument(
+                ErrorContext::new(
+                    "The items have already been read from standard input, which can only be \
+                    read once.",
+                )
+                .with_value(path)
+                .with_span(0, path.len())
+                .with_help("put all the items from standard input in one source, or use a file"),
+            ));
+        }
+        let mut source = String::new();
+        io::stdin().read_to_string(&mut source).map_err(|e| {
+            SplittingError::FileError(ErrorContext::new(format!(
+                "Could not read the items from standard input: {}",
+                e
+            )))
+        })?;
+        (STDIN, STDIN_SOURCE.get_or_init(|| source).clone())
+    } else {
+        let source = fs::read_to_string(path).map_err(|e| {
+            SplittingError::FileError(
+                ErrorContext::new(format!("Could not read {}: {}", path, e)).with_value(path),
+            )
+        })?;
+        (path, source)
+    };
+    let records = parse_item_records(&source, &receipt.locale).map_err(|e| e.in_file(file))?;
+    for record in records {
+        receipt
+            .parse_add_named_item(&record.name, &record.pattern)
+            .map_err(|e| {
+                e.shift_span(record.offset)
+                    .in_argument(record.line_no)
+                    .in_file(file)
+            })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::records::{add_item_records, parse_item_records, ItemRecord, STDIN_SOURCE};
+    use crate::core::locale::Locale;
+    use crate::core::receipt::{InputSpan, Receipt, SplittingError};
+
+    fn names_and_patterns(source: &str, locale: &str) -> Vec<(String, String)> {
+        parse_item_records(source, &locale.parse().unwrap())
+            .unwrap()
+            .into_iter()
+            .map(|record| (record.name, record.pattern))
+            .collect()
+    }
+
+    #[test]
+    fn parse_line_records() {
+        let source = "# From the till\nFood 200 Al,B,M\n\n  Red wine  50 Al:2,B\nCake 3*4,Al,B\n";
+        assert_eq!(
+            names_and_patterns(source, "en"),
+            vec![
+                ("Food".into(), "200,Al,B,M".into()),
+                ("Red wine".into(), "50,Al:2,B".into()),
+                ("Cake".into(), "3*4,Al,B".into()),
+            ]
+        );
+        // Patterns are padded to keep the spans of the line.
+        assert_eq!(
+            parse_item_records("Tea 3  A;B", &"de".parse().unwrap()).unwrap(),
+            vec![ItemRecord {
+                line_no: 1,
+                name: "Tea".into(),
+                pattern: "3 ;A;B".into(),
+                offset: 4,
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_csv_records() {
+        let source = "Item,Price,People\nFood,200,\"Al,B,M\"\n\"Wine, red\",50,\"Al:2,B\"";
+        assert_eq!(
+            names_and_patterns(source, "en"),
+            vec![
+                ("Food".into(), "200 ,Al,B,M".into()),
+                ("Wine, red".into(), "50 ,Al:2,B".into()),
+            ]
+        );
+        assert_eq!(
+            names_and_patterns("name,value,abbrevs\nFood,\"1,200\",\"Al,B\"", "en"),
+            vec![("Food".into(), "\"1,200\" ,Al,B".into())]
+        );
+        let source = "abbrevs;name;value\nAl;Tea \"Earl\";12,50";
+        assert_eq!(
+            names_and_patterns(source, "de"),
+            vec![("Tea \"Earl\"".into(), "12,50;Al".into())]
+        );
+
+        let error = parse_item_records("name,value\nFood,200", &Locale::default()).unwrap_err();
+        assert_eq!(error.context().argument, Some(1));
+        let error =
+            parse_item_records("name,value,abbrevs\nFood,200", &Locale::default()).unwrap_err();
+        assert_eq!(error.context().argument, Some(2));
+    }
+
+    #[test]
+    fn record_errors_point_at_their_line() {
+        let mut receipt = Receipt::parse_create_receipt("300,Alice,Bob").unwrap();
+        let records =
+            parse_item_records("Food 200 Al,B\nWine 50 Al,Xy", &Locale::default()).unwrap();
+        let record = &records[1];
+        let error = receipt
+            .parse_add_named_item(&record.name, &record.pattern)
+            .map_err(|e| e.shift_span(record.offset).in_argument(record.line_no))
+            .unwrap_err();
+        assert_eq!(error.context().argument, Some(2));
+        assert_eq!(error.context().span, Some(InputSpan { start: 11, end: 13 }));
+    }
+
+    #[test]
+    fn read_standard_input_once() {
+        let mut receipt = Receipt::parse_create_receipt("300,Alice,Bob").unwrap();
+        STDIN_SOURCE.get_or_init(|| "Food 200 Al,B\n".into());
+        assert!(matches!(
+            add_item_records(&mut receipt, "-"),
+            Err(SplittingError::InvalidArgument(_))
+        ));
+        assert!(receipt.items.is_empty());
+    }
+}
@@ -0,0 +1,536 @@
+use crate::cli::display::OutputFormat;
+use crate::cli::tokenizer::{self, unquote};
+use crate::cli::utils as parse_utils;
+use crate::cli::{arg_parser, diagnostic};
+use crate::core::history::{EditHistory, ReceiptEdit};
+use crate::core::locale::Locale;
+use crate::core::receipt::{ErrorContext, Receipt, SplittingError};
+use rust_decimal::Decimal;
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::process::ExitCode;
+
+const COMMANDS: [&str; 17] = [
+    "new", "add", "tax", "tip", "service", "prop", "paid", "rename", "value", "remove", "toggle",
+    "total", "undo", "redo", "list", "show", "help",
+];
+
+const HELP: &str = "\
+  new Total,Person_1,Person_2[,...]    Start a new receipt, in the locale of the last
+                                       one unless --locale, --delimiter or --currency
+                                       follow it
+  add Name Value,Abbrev_1[,...]        Add an item, with the same pattern as the
+                                       command line (Al:2, Al:25% and Al=30 included)
+  tax|tip|service Amount[%]            Add an item split in proportion to the items
+  prop Name=Amount[%][,Abbrev,...]     Add any other proportional item
+  paid Abbrev,Amount[,Method]          Record a payment
+  rename N Name                        Rename item N
+  value N Value                        Change the value of item N
+  remove N                             Remove item N
+  toggle N Abbrev                      Add or remove a person sharing item N
+  total Value                          Change the receipt's total
+  undo, redo                           Undo or redo the last change
+  list                                 List the items
+  show [Format]                        Print the splits (table, ascii, markdown, ...)
+  help                                 Print this help
+  quit, exit                           Leave (or press Ctrl-D)
+
+Press Tab to complete commands and the names of the people sharing the receipt.";
+
+// What the session wants printed after a command.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Print(String),
+    Quit,
+}
+
+fn invalid_command(message: String) -> SplittingError {
+    SplittingError::InvalidArgument(ErrorContext::new(message))
+}
+
+// Item numbers are shown (and typed) starting from 1.
+fn parse_item_number(receipt: &Receipt, number: &str) -> Result<usize, SplittingError> {
+    match number.parse::<usize>() {
+        Ok(number) if (1..=receipt.items.len()).contains(&number) => Ok(number - 1),
+        _ => Err(SplittingError::InvalidArgument(
+            ErrorContext::new(format!(
+                "{} is not an item number. Use 'list' to see the {} items.",
+                number,
+                receipt.items.len()
+            ))
+            .with_value(number),
+        )),
+    }
+}
+
+// Same wording as the balance shown in the web app, with amounts in the receipt's locale.
+pub fn describe_balance(receipt: &Receipt) -> String {
+    let (_, balance) = receipt.get_itemized_total_and_leftover();
+    let locale = &receipt.locale;
+    if balance > Decimal::ZERO {
+        format!("+{} left to balance", locale.format_money(balance))
+    } else if balance < Decimal::ZERO {
+        format!(
+            "Remaining: {}. Item total exceeds receipt total.",
+            locale.format_money(balance)
+        )
+    } else {
+        "0. Perfectly balanced, as all things should be.".to_string()
+    }
+}
+
+// A receipt being built interactively. Every change goes through the edit history, so
+// that it can be undone.
+#[derive(Default)]
+pub struct Session {
+    history: Option<EditHistory>,
+}
+
+impl Session {
+    // A session that starts from a receipt, rather than from 'new'.
+    pub fn with_receipt(receipt: Receipt) -> Self {
+        Session {
+            history: Some(EditHistory::new(receipt)),
+        }
+    }
+
+    pub fn people(&self) -> &[String] {
+        match &self.history {
+            Some(history) => &history.receipt().shared_by,
+            None => &[],
+        }
+    }
+
+    fn history(&mut self) -> Result<&mut EditHistory, SplittingError> {
+        self.history.as_mut().ok_or_else(|| {
+            invalid_command(
+                "There is no receipt yet. Start one with 'new Total,Person_1,Person_2'".into(),
+            )
+        })
+    }
+
+    // Parse a pattern on a copy of the receipt, to find out what the edit should be
+    // without changing the receipt outside of the history.
+    fn parse_edit(
+        &mut self,
+        parse: impl FnOnce(&mut Receipt) -> Result<(), SplittingError>,
+        to_edit: impl FnOnce(&Receipt) -> ReceiptEdit,
+    ) -> Result<(), SplittingError> {
+        let history = self.history()?;
+        let mut receipt = history.receipt().clone();
+        parse(&mut receipt)?;
+        history.apply(to_edit(&receipt))
+    }
+
+    fn add_item_edit(receipt: &Receipt) -> ReceiptEdit {
+        let item = receipt.items.last().unwrap();
+        if item.is_prop_dist {
+            ReceiptEdit::AddProportionalItem {
+                value: item.value,
+                name: item.name.clone(),
+                shared_by: item.shared_by.clone(),
+            }
+        } else {
+            ReceiptEdit::AddItem {
+                value: item.value,
+                name: item.name.clone(),
+                shared_by: item.shared_by.clone(),
+                share_ratio: Some(item.share_ratio.clone()),
+            }
+        }
+    }
+
+    fn list_items(&mut self) -> Result<String, SplittingError> {
+        let receipt = self.history()?.receipt();
+        let mut lines: Vec<String> = receipt
+            .items
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| {
+                format!(
+                    "{:>3}. {} {} ({})",
+                    idx + 1,
+                    item.name,
+                    receipt.locale.format_money(item.value),
+                    item.shared_by.join(", ")
+                )
+            })
+            .collect();
+        for payment in receipt.payments.iter() {
+            lines.push(format!(
+                "     {} paid {} by {}",
+                payment.payer,
+                receipt.locale.format_money(payment.amount),
+                payment.method
+            ));
+        }
+        if lines.is_empty() {
+            lines.push("There are no items yet.".into());
+        }
+        Ok(lines.join("\n"))
+    }
+
+    // Run a single command. Changes are followed by the balance of the receipt.
+    pub fn execute(&mut self, line: &str) -> Result<Outcome, SplittingError> {
+        let line = line.trim();
+        let (command, args) = match line.split_once(char::is_whitespace) {
+            Some((command, args)) => (command, args.trim()),
+            None => (line, ""),
+        };
+        let missing = |usage: &str| invalid_command(format!("Usage: {}", usage));
+
+        match command {
+            "" => return Ok(Outcome::Print(String::new())),
+            "quit" | "exit" => return Ok(Outcome::Quit),
+            "help" => return Ok(Outcome::Print(HELP.into())),
+            "list" => return self.list_items().map(Outcome::Print),
+            "show" => {
+                let format = match args {
+                    "" => OutputFormat::Table,
+                    format => format.parse()?,
+                };
+                // Every issue is listed, and the splits are only shown if none of them stop
+                // the receipt from being split.
+                let receipt = self.history()?.receipt();
+                let mut lines: Vec<String> = receipt
+                    .validate()
+                    .iter()
+                    .map(|issue| issue.to_string())
+                    .collect();
+                if !receipt.has_validation_errors() {
+                    lines.push(receipt.render_splits(format)?);
+                }
+                return Ok(Outcome::Print(lines.join("\n")));
+            }
+            "new" => {
+                if args.is_empty() {
+                    return Err(missing("new Total,Person_1,Person_2[,...]"));
+                }
+                // Read as the receipt that the session started from, with the options
+                // that can follow it, and in the locale of the receipt it replaces.
+                let mut words = vec![command.to_string()];
+                match args.split_once(" --") {
+                    Some((pattern, options)) => {
+                        words.push(pattern.trim().to_string());
+                        words.extend(
+                            format!("--{}", options)
+                                .split_whitespace()
+                                .map(String::from),
+                        );
+                    }
+                    None => words.push(args.to_string()),
+                }
+                let locale = match &self.history {
+                    Some(history) => history.receipt().locale.clone(),
+                    None => Locale::default(),
+                };
+                self.history =
+                    arg_parser::parse_starting_receipt(&words, locale)?.map(EditHistory::new);
+            }
+            "add" => match tokenizer::split_name_and_pattern(args)? {
+                Some((name, _, pattern)) => self.parse_edit(
+                    |receipt| receipt.parse_add_named_item(&name.text, pattern),
+                    Self::add_item_edit,
+                )?,
+                None => return Err(missing("add Name Value,Abbrev_1[,...]")),
+            },
+            "tax" | "tip" | "service" | "prop" => {
+                let (name, pattern) = match command {
+                    "prop" => tokenizer::split_once_raw(args, |c| c == '=')?
+                        .map(|(name, _, pattern)| (unquote(name.trim()), pattern))
+                        .ok_or_else(|| missing("prop Name=Amount[%][,Abbrev,...]"))?,
+                    _ => {
+                        let mut name = command.to_string();
+                        name[..1].make_ascii_uppercase();
+                        (name, args)
+                    }
+                };
+                self.parse_edit(
+                    |receipt| receipt.parse_add_proportional_item(&name, pattern),
+                    Self::add_item_edit,
+                )?
+            }
+            "paid" => self.parse_edit(
+                |receipt| receipt.parse_add_payment(args),
+                |receipt| {
+                    let payment = receipt.payments.last().unwrap();
+                    ReceiptEdit::AddPayment {
+                        payer: payment.payer.clone(),
+                        amount: payment.amount,
+                        method: payment.method.clone(),
+                    }
+                },
+            )?,
+            "rename" | "value" | "toggle" => {
+                let (number, rest) = args
+                    .split_once(char::is_whitespace)
+                    .ok_or_else(|| missing(&format!("{} N ...", command)))?;
+                let history = self.history()?;
+                let item_idx = parse_item_number(history.receipt(), number)?;
+                let rest = rest.trim();
+                let edit = match command {
+                    "rename" => ReceiptEdit::EditItemName {
+                        item_idx,
+                        name: rest.to_string(),
+                    },
+                    "value" => ReceiptEdit::EditItemValue {
+                        item_idx,
+                        value: parse_utils::parse_decimal(rest, 0, &history.receipt().locale)?,
+                    },
+                    _ => {
+                        // Abbreviations are matched as in patterns, on a copy of the receipt.
+                        let mut receipt = history.receipt().clone();
+                        let person = receipt.parse_person(rest)?;
+                        ReceiptEdit::ToggleSharer { item_idx, person }
+                    }
+                };
+                history.apply(edit)?;
+            }
+            "remove" => {
+                let history = self.history()?;
+                let item_idx = parse_item_number(history.receipt(), args)?;
+                history.apply(ReceiptEdit::RemoveItem { item_idx })?;
+            }
+            "total" => {
+                let history = self.history()?;
+                let value = parse_utils::parse_decimal(args, 0, &history.receipt().locale)?;
+                history.apply(ReceiptEdit::ChangeTotal { value })?;
+            }
+            "undo" | "redo" => {
+                let history = self.history()?;
+                let changed = match command {
+                    "undo" => history.undo()?,
+                    _ => history.redo()?,
+                };
+                if !changed {
+                    return Ok(Outcome::Print(format!("Nothing to {}.", command)));
+                }
+            }
+            _ => {
+                return Err(SplittingError::InvalidArgument(
+                    ErrorContext::new(format!(
+                        "{} is not a command. Type 'help' to see them all.",
+                        command
+                    ))
+                    .with_value(command),
+                ))
+            }
+        }
+
+        let receipt = self.history()?.receipt();
+        Ok(Outcome::Print(describe_balance(receipt)))
+    }
+}
+
+// Completes commands at the start of the line, and people's names anywhere else.
+struct SessionHelper {
+    people: Vec<String>,
+}
+
+impl Completer for SessionHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos].rfind([' ', ',', '=']).map_or(0, |idx| idx + 1);
+        let word = &line[start..pos];
+        let candidates: Vec<&str> = if line[..start].trim().is_empty() {
+            COMMANDS.to_vec()
+        } else {
+            self.people.iter().map(|person| person.as_str()).collect()
+        };
+        let matches = candidates
+            .into_iter()
+            .filter(|candidate| candidate.to_lowercase().starts_with(&word.to_lowercase()))
+            .map(|candidate| Pair {
+                display: candidate.to_string(),
+                replacement: candidate.to_string(),
+            })
+            .collect();
+        Ok((start, matches))
+    }
+}
+
+impl Hinter for SessionHelper {
+    type Hint = String;
+}
+
+impl Highlighter for SessionHelper {}
+
+impl Validator for SessionHelper {}
+
+impl Helper for SessionHelper {}
+
+// Build a receipt one command at a time, optionally starting from a receipt.
+pub fn run(receipt: Option<Receipt>) -> ExitCode {
+    let mut session = match receipt {
+        Some(receipt) => Session::with_receipt(receipt),
+        None => Session::default(),
+    };
+    let mut editor: Editor<SessionHelper, DefaultHistory> = match Editor::new() {
+        Ok(editor) => editor,
+        Err(error) => {
+            eprintln!("error: could not start the interactive mode: {}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+    editor.set_helper(Some(SessionHelper { people: vec![] }));
+
+    println!("Type 'help' to see the commands, and 'quit' to leave.");
+    if let Some(history) = &session.history {
+        println!("{}", describe_balance(history.receipt()));
+    }
+    loop {
+        if let Some(helper) = editor.helper_mut() {
+            helper.people = session.people().to_vec();
+        }
+        let line = match editor.readline("borrow-checker> ") {
+            Ok(line) => {
+                let _ = editor.add_history_entry(line.as_str());
+                line
+            }
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(error) => {
+                eprintln!("error: {}", error);
+                return ExitCode::FAILURE;
+            }
+        };
+
+        match session.execute(&line) {
+            Ok(Outcome::Print(output)) => {
+                if !output.is_empty() {
+                    println!("{}", output);
+                }
+            }
+            Ok(Outcome::Quit) => break,
+            Err(error) => eprintln!("{}", diagnostic::render_error(&error, None)),
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::repl::{Outcome, Session};
+    use crate::core::receipt::SplittingError;
+    use rust_decimal::prelude::*;
+
+    fn run(session: &mut Session, line: &str) -> String {
+        match session.execute(line).unwrap() {
+            Outcome::Print(output) => output,
+            Outcome::Quit => panic!("{} quit the session", line),
+        }
+    }
+
+    #[test]
+    fn build_a_receipt() {
+        let mut session = Session::default();
+        assert!(session.execute("add Food 200,Al").is_err());
+
+        run(&mut session, "new 300,Alice,Bob,Marshall");
+        assert_eq!(session.people(), ["Alice", "Bob", "Marshall"]);
+        assert_eq!(
+            run(&mut session, "add Fried rice 200,Al,B,M"),
+            "+100.00 left to balance"
+        );
+        run(&mut session, "add Wine 50,Al:2,B");
+        run(&mut session, "tax 10%");
+        run(&mut session, "toggle 2 M");
+        run(&mut session, "rename 1 Rice");
+        run(&mut session, "value 1 210");
+        let receipt = session.history.as_ref().unwrap().receipt();
+        assert_eq!(receipt.items[0].name, "Rice");
+        assert_eq!(receipt.items[0].value, dec![210]);
+        assert_eq!(receipt.items[1].shared_by, vec!["Alice", "Bob", "Marshall"]);
+        assert_eq!(receipt.items[2].name, "Tax (10%)");
+
+        run(&mut session, "undo");
+        run(&mut session, "undo");
+        let receipt = session.history.as_ref().unwrap().receipt();
+        assert_eq!(receipt.items[0].name, "Fried rice");
+        run(&mut session, "remove 3");
+        assert_eq!(
+            run(&mut session, "total 250"),
+            "0. Perfectly balanced, as all things should be."
+        );
+        run(&mut session, "paid B,250,Card");
+        assert_eq!(
+            run(&mut session, "list"),
+            "  1. Fried rice 200.00 (Alice, Bob, Marshall)\n  \
+            2. Wine 50.00 (Alice, Bob, Marshall)\n     Bob paid 250.00 by card"
+        );
+        assert!(run(&mut session, "show csv").contains("<net>"));
+        assert_eq!(session.execute("quit").unwrap(), Outcome::Quit);
+    }
+
+    #[test]
+    fn invalid_commands_leave_the_receipt_alone() {
+        let mut session = Session::default();
+        run(&mut session, "new 300,Alice,Bob");
+        run(&mut session, "add Food 200,Al,B");
+        for line in ["add Wine 50,Xy", "remove 2", "value 1 abc", "frobnicate"] {
+            assert!(session.execute(line).is_err(), "{}", line);
+        }
+        assert!(matches!(
+            session.execute("toggle 1 Zz"),
+            Err(SplittingError::InvalidAbbreviation(_))
+        ));
+        assert_eq!(session.history.as_ref().unwrap().edits().len(), 1);
+        assert_eq!(run(&mut session, "redo"), "Nothing to redo.");
+    }
+
+    #[test]
+    fn receipts_that_cannot_be_split_keep_the_session() {
+        let mut session = Session::default();
+        run(&mut session, "new 300,Alice,Bob");
+        let shown = run(&mut session, "show");
+        assert!(shown.contains("error: The leftover amount 300 cannot be split"));
+        assert!(!shown.contains("<total>"));
+
+        for line in ["tip 10%", "tip 10", "prop Delivery=5,Al"] {
+            assert!(matches!(
+                session.execute(line),
+                Err(SplittingError::InvalidShareConfiguration(_))
+            ));
+        }
+
+        run(&mut session, "add Food 300,Al,B");
+        run(&mut session, "value 1 0");
+        let shown = run(&mut session, "show");
+        assert!(shown.contains("error (item 1): Food does not have a value yet."));
+        run(&mut session, "undo");
+        assert!(run(&mut session, "show").contains("<total>"));
+        assert_eq!(session.history.as_ref().unwrap().edits().len(), 1);
+    }
+
+    #[test]
+    fn new_receipts_keep_the_locale() {
+        let mut session = Session::default();
+        run(&mut session, "new 12,50;Alice;Bob --locale de --currency €");
+        assert_eq!(
+            run(&mut session, "add Food 10;Al"),
+            "+2,50 € left to balance"
+        );
+
+        // The next receipt is in the same locale, unless it is given another.
+        run(&mut session, "new 7,50;Alice;Bob");
+        let receipt = session.history.as_ref().unwrap().receipt();
+        assert_eq!(receipt.value, Decimal::new(750, 2));
+        run(&mut session, "new 7.50,Alice,Bob --locale en");
+        let receipt = session.history.as_ref().unwrap().receipt();
+        assert_eq!(receipt.value, Decimal::new(750, 2));
+
+        assert!(session.execute("new 300,Al,B -Food 200,Al").is_err());
+        assert!(session.execute("new 300,Al,B --locale").is_err());
+    }
+}
@@ -0,0 +1,381 @@
+use crate::cli::display::RowKind;
+use crate::cli::tokenizer;
+use crate::core::alias::AliasTable;
+use crate::core::locale::Locale;
+use crate::core::receipt::{ErrorContext, Person, Receipt, SplittingError};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// What the CLI keeps between runs, as files in directories of its own:
+//
+//     groups.toml     the people of each saved group, and their aliases, in the
+//                     borrow-checker directory of the user's configuration directory
+//     history.json    the splits saved with --save, oldest first, in the
+//                     borrow-checker directory of the user's data directory
+//
+// Both are kept in $BORROW_CHECKER_HOME instead, if it is set.
+pub struct Store {
+    config: PathBuf,
+    data: PathBuf,
+}
+
+// People who often share receipts, referred to as @name in a receipt's pattern, and
+// the aliases they go by.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Group {
+    pub people: Vec<Person>,
+    #[serde(default, skip_serializing_if = "AliasTable::is_empty")]
+    pub aliases: AliasTable,
+}
+
+impl Group {
+    // The people of the group, as they would be written in a pattern.
+    pub fn people_pattern(&self, delimiter: char) -> String {
+        let people: Vec<String> = self
+            .people
+            .iter()
+            .map(|person| tokenizer::escape(person, delimiter))
+            .collect();
+        people.join(&delimiter.to_string())
+    }
+}
+
+// A split as it was saved: the arguments it was made from, and what each person owed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedSplit {
+    pub name: String,
+    // Seconds since the Unix epoch.
+    pub saved_at: u64,
+    pub args: Vec<String>,
+    pub total: Decimal,
+    pub people: Vec<Person>,
+    pub owed: Vec<Decimal>,
+    // The locale the amounts are shown in. Splits saved before it was kept are in the
+    // default one.
+    #[serde(default)]
+    pub locale: Locale,
+}
+
+impl SavedSplit {
+    pub fn new(name: &str, args: &[String], receipt: &Receipt) -> Result<Self, SplittingError> {
+        let owed = receipt
+            .split_rows()?
+            .into_iter()
+            .find(|row| row.kind == RowKind::Total)
+            .map_or_else(Vec::new, |row| row.splits);
+        Ok(SavedSplit {
+            name: name.to_string(),
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs()),
+            args: args.to_vec(),
+            total: receipt.value,
+            people: receipt.shared_by.clone(),
+            owed,
+            locale: receipt.locale.clone(),
+        })
+    }
+}
+
+fn file_error(path: &Path, reason: impl std::fmt::Display) -> SplittingError {
+    SplittingError::FileError(
+        ErrorContext::new(format!("Could not use {}: {}", path.display(), reason))
+            .with_value(path.display()),
+    )
+}
+
+// A time since the Unix epoch as a UTC date and time, such as 2024-05-01 18:30.
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let minutes = secs % 86400 / 60;
+    // Civil dates from days since the epoch, counting in 400-year eras from March 0000.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+impl Store {
+    pub fn open() -> Result<Store, SplittingError> {
+        if let Some(dir) = env::var_os("BORROW_CHECKER_HOME") {
+            return Ok(Store::at(dir));
+        }
+        match (dirs::config_dir(), dirs::data_dir()) {
+            (Some(config), Some(data)) => Ok(Store {
+                config: config.join("borrow-checker"),
+                data: data.join("borrow-checker"),
+            }),
+            _ => Err(SplittingError::FileError(
+                ErrorContext::new("There is no directory to save to")
+                    .with_help("set BORROW_CHECKER_HOME to a directory to save to"),
+            )),
+        }
+    }
+
+    // A store kept entirely in one directory.
+    pub fn at(dir: impl Into<PathBuf>) -> Store {
+        let dir = dir.into();
+        Store {
+            config: dir.clone(),
+            data: dir,
+        }
+    }
+
+    // The contents of a file of the store, or None if nothing has been saved to it.
+    fn read(path: PathBuf) -> Result<Option<(PathBuf, String)>, SplittingError> {
+        match fs::read_to_string(&path) {
+            Ok(source) => Ok(Some((path, source))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(file_error(&path, e)),
+        }
+    }
+
+    fn write(path: PathBuf, contents: &str) -> Result<(), SplittingError> {
+        path.parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, contents))
+            .map_err(|e| file_error(&path, e))
+    }
+
+    pub fn groups(&self) -> Result<BTreeMap<String, Group>, SplittingError> {
+        match Store::read(self.config.join("groups.toml"))? {
+            Some((path, source)) => {
+                toml::from_str(&source).map_err(|e| file_error(&path, e.message()))
+            }
+            None => Ok(BTreeMap::new()),
+        }
+    }
+
+    fn write_groups(&self, groups: &BTreeMap<String, Group>) -> Result<(), SplittingError> {
+        let source = toml::to_string(groups)
+            .map_err(|e| SplittingError::InternalError(e.to_string().into()))?;
+        Store::write(self.config.join("groups.toml"), &source)
+    }
+
+    fn unknown_group(name: &str) -> SplittingError {
+        SplittingError::InvalidArgument(
+            ErrorContext::new(format!("There is no group called {}", name))
+                .with_value(name)
+                .with_help("see the saved groups with 'borrow-checker groups'"),
+        )
+    }
+
+    pub fn group(&self, name: &str) -> Result<Group, SplittingError> {
+        self.groups()?
+            .remove(name)
+            .ok_or_else(|| Store::unknown_group(name))
+    }
+
+    // Save the people of a group, keeping the aliases of any group of the same name.
+    pub fn save_group(&self, name: &str, people: Vec<Person>) -> Result<(), SplittingError> {
+        let mut groups = self.groups()?;
+        groups.entry(name.to_string()).or_default().people = people;
+        self.write_groups(&groups)
+    }
+
+    // Change the aliases of a group.
+    pub fn update_aliases(
+        &self,
+        name: &str,
+        update: impl FnOnce(&mut AliasTable) -> Result<(), SplittingError>,
+    ) -> Result<(), SplittingError> {
+        let mut groups = self.groups()?;
+        let group = groups
+            .get_mut(name)
+            .ok_or_else(|| Store::unknown_group(name))?;
+        update(&mut group.aliases)?;
+        self.write_groups(&groups)
+    }
+
+    // Returns whether there was a group of that name.
+    pub fn remove_group(&self, name: &str) -> Result<bool, SplittingError> {
+        let mut groups = self.groups()?;
+        let removed = groups.remove(name).is_some();
+        if removed {
+            self.write_groups(&groups)?;
+        }
+        Ok(removed)
+    }
+
+    pub fn history(&self) -> Result<Vec<SavedSplit>, SplittingError> {
+        match Store::read(self.data.join("history.json"))? {
+            Some((path, source)) => serde_json::from_str(&source).map_err(|e| file_error(&path, e)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn write_history(&self, history: &[SavedSplit]) -> Result<(), SplittingError> {
+        let source = serde_json::to_string_pretty(history)
+            .map_err(|e| SplittingError::InternalError(e.to_string().into()))?;
+        Store::write(self.data.join("history.json"), &source)
+    }
+
+    pub fn save_split(&self, split: SavedSplit) -> Result<(), SplittingError> {
+        let mut history = self.history()?;
+        history.push(split);
+        self.write_history(&history)
+    }
+
+    pub fn clear_history(&self) -> Result<(), SplittingError> {
+        self.write_history(&[])
+    }
+}
+
+// Replace every @name among the people of a receipt's pattern with the people of the
+// saved group, returning the pattern and the aliases of the groups. Quoted names are
+// never group references, so '"@home"' is a person.
+pub fn expand_groups(
+    pattern: &str,
+    delimiter: char,
+    groups: impl Fn(&str) -> Result<Group, SplittingError>,
+) -> Result<(String, AliasTable), SplittingError> {
+    let mut fields: Vec<String> = Vec::new();
+    let mut aliases = AliasTable::new();
+    for (idx, (start, field)) in tokenizer::split_raw(pattern, delimiter)?
+        .into_iter()
+        .enumerate()
+    {
+        match field.trim().strip_prefix('@') {
+            Some(name) if idx > 0 => {
+                let group = groups(name).map_err(|e| e.with_span(start, start + field.len()))?;
+                fields.push(group.people_pattern(delimiter));
+                aliases.extend(&group.aliases);
+            }
+            _ => fields.push(field.to_string()),
+        }
+    }
+    Ok((fields.join(&delimiter.to_string()), aliases))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::store::{expand_groups, format_timestamp, Group, SavedSplit, Store};
+    use crate::core::alias::AliasTable;
+    use crate::core::receipt::{InputSpan, Receipt};
+    use crate::utils;
+    use rust_decimal::prelude::*;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn save_groups_and_history() {
+        let dir = env::temp_dir().join(format!("borrow-checker-store-{}", std::process::id()));
+        let store = Store::at(&dir);
+        assert!(store.groups().unwrap().is_empty());
+        assert!(store.history().unwrap().is_empty());
+
+        store
+            .save_group("flatmates", utils::strs_to_strings(vec!["Alice", "Bob"]))
+            .unwrap();
+        store
+            .save_group("work", utils::strs_to_strings(vec!["Marshall"]))
+            .unwrap();
+        assert!(store.remove_group("work").unwrap());
+        assert!(!store.remove_group("work").unwrap());
+        let groups = store.groups().unwrap();
+        assert_eq!(groups.keys().collect::<Vec<_>>(), vec!["flatmates"]);
+        assert_eq!(groups["flatmates"].people, vec!["Alice", "Bob"]);
+
+        store
+            .update_aliases("flatmates", |aliases| aliases.parse_insert("Ally=Alice"))
+            .unwrap();
+        assert!(store.update_aliases("work", |_| Ok(())).is_err());
+        // Saving the people of a group again keeps its aliases.
+        store
+            .save_group(
+                "flatmates",
+                utils::strs_to_strings(vec!["Alice", "Bob", "Cy"]),
+            )
+            .unwrap();
+        let flatmates = store.group("flatmates").unwrap();
+        assert_eq!(flatmates.people.len(), 3);
+        assert_eq!(flatmates.aliases.get("Ally").unwrap(), "Alice");
+
+        let mut receipt = Receipt::new(dec![300], vec!["Alice", "Bob"]).unwrap();
+        receipt.locale = "de".parse().unwrap();
+        receipt
+            .add_item_split_by_ratio(
+                dec![200],
+                "Food".into(),
+                utils::strs_to_strings(vec!["Alice", "Bob"]),
+                Some(vec![dec![3], dec![1]]),
+            )
+            .unwrap();
+        let args = utils::strs_to_strings(vec!["300,Alice,Bob", "--Food", "200,A:3,B"]);
+        store
+            .save_split(SavedSplit::new("Dinner", &args, &receipt).unwrap())
+            .unwrap();
+        let history = store.history().unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].args, args);
+        assert_eq!(history[0].owed, vec![dec![225], dec![75]]);
+        assert_eq!(history[0].locale.format_money(history[0].total), "300,00");
+
+        store.clear_history().unwrap();
+        assert!(store.history().unwrap().is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn expand_group_references() {
+        let mut aliases = AliasTable::new();
+        aliases.parse_insert("Ally=Alice").unwrap();
+        let groups = |name: &str| match name {
+            "flatmates" => Ok(Group {
+                people: utils::strs_to_strings(vec!["Alice", "Smith, J"]),
+                aliases: aliases.clone(),
+            }),
+            _ => Err(Store::unknown_group(name)),
+        };
+
+        let (pattern, expanded_aliases) = expand_groups("300,@flatmates,Bob", ',', groups).unwrap();
+        assert_eq!(pattern, "300,Alice,Smith\\, J,Bob");
+        assert_eq!(expanded_aliases, aliases);
+        assert_eq!(
+            expand_groups("12,50;\"@home\";@flatmates", ';', groups)
+                .unwrap()
+                .0,
+            "12,50;\"@home\";Alice;Smith\\, J"
+        );
+
+        // A delimiter in a name is escaped, whatever the delimiter is.
+        let group = Group {
+            people: utils::strs_to_strings(vec!["Lee; Sam", "Bob"]),
+            ..Default::default()
+        };
+        assert_eq!(group.people_pattern(';'), "Lee\\; Sam;Bob");
+
+        let error = expand_groups("300,Bob,@work", ',', groups).unwrap_err();
+        assert_eq!(error.context().span, Some(InputSpan { start: 8, end: 13 }));
+    }
+
+    #[test]
+    fn format_timestamps_as_utc() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+        assert_eq!(format_timestamp(951_827_696), "2000-02-29 12:34");
+        assert_eq!(format_timestamp(1_714_588_200), "2024-05-01 18:30");
+    }
+}
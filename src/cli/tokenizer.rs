@@ -0,0 +1,241 @@
+use crate::core::receipt::{ErrorContext, SplittingError};
+
+// Tokenizing of the patterns given on the command line, in receipt files and in the
+// interactive mode. The grammar is:
+//
+//     receipt  = amount "," person { "," person }
+//     item     = amount "," share { "," share }
+//     share    = abbrev [ ":" weight | ":" percent "%" | "=" amount ]
+//     payment  = abbrev "," amount [ "," method ]
+//
+// Any part may be quoted with double quotes, in which commas, colons and equals signs
+// are taken literally, so that '300,"Smith, J",Bob' is shared by "Smith, J" and Bob.
+// Outside or inside quotes, a backslash takes the next character literally, as in
+// 'Fish\, chips' or '"Say \"cheese\""'.
+//
+// On the command line, an item's name follows a dash (-) or a double dash (--). After a
+// lone '--', every pair of arguments is an item's name and its pattern, so names may
+// start with a dash, as in '-- "-20% promo" -20,Al,B'.
+
+// A part of a pattern with its quotes and escapes removed, along with the byte range of
+// the part as it was written.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Field {
+    pub fn new(raw: &str, start: usize) -> Self {
+        Field {
+            text: unquote(raw),
+            start,
+            end: start + raw.len(),
+        }
+    }
+
+    pub fn shifted(mut self, offset: usize) -> Self {
+        self.start += offset;
+        self.end += offset;
+        self
+    }
+}
+
+// Positions of the separators that are neither quoted nor escaped.
+fn separator_positions(
+    input: &str,
+    is_separator: impl Fn(char) -> bool,
+) -> Result<Vec<usize>, SplittingError> {
+    let mut positions: Vec<usize> = Vec::new();
+    let mut quote_start: Option<usize> = None;
+    let mut is_escaped = false;
+    for (idx, c) in input.char_indices() {
+        if is_escaped {
+            is_escaped = false;
+        } else if c == '\\' {
+            is_escaped = true;
+        } else if c == '"' {
+            quote_start = match quote_start {
+                Some(_) => None,
+                None => Some(idx),
+            };
+        } else if quote_start.is_none() && is_separator(c) {
+            positions.push(idx);
+        }
+    }
+
+    if is_escaped {
+        return Err(SplittingError::InvalidArgument(
+            ErrorContext::new(format!(
+                "{} ends with a backslash, which has nothing to escape.",
+                input
+            ))
+            .with_value(input)
+            .with_span(input.len() - 1, input.len())
+            .with_help("escape a backslash with another one, as in '\\\\'"),
+        ));
+    }
+    if let Some(start) = quote_start {
+        return Err(SplittingError::InvalidArgument(
+            ErrorContext::new(format!("The quote in {} is never closed.", input))
+                .with_value(input)
+                .with_span(start, input.len())
+                .with_help("close the quote, or escape it with a backslash, as in '\\\"'"),
+        ));
+    }
+    Ok(positions)
+}
+
+// Remove the quotes and escapes from a part of a pattern that is known to be well formed.
+pub fn unquote(raw: &str) -> String {
+    let mut text = String::with_capacity(raw.len());
+    let mut is_escaped = false;
+    for c in raw.chars() {
+        if is_escaped {
+            text.push(c);
+            is_escaped = false;
+        } else if c == '\\' {
+            is_escaped = true;
+        } else if c != '"' {
+            text.push(c);
+        }
+    }
+    text
+}
+
+// The inverse of unquote, escaping anything that would otherwise be read as part of the
+// grammar, including the delimiter of the pattern the text is written into.
+pub fn escape(text: &str, delimiter: char) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '"' | ',' | ':' | '=') || c == delimiter {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// Split on every separator that is neither quoted nor escaped, keeping the quotes and
+// escapes in each part, along with the byte offset of the part.
+pub fn split_raw(input: &str, separator: char) -> Result<Vec<(usize, &str)>, SplittingError> {
+    let mut start = 0;
+    let mut parts: Vec<(usize, &str)> = Vec::new();
+    for position in separator_positions(input, |c| c == separator)? {
+        parts.push((start, &input[start..position]));
+        start = position + separator.len_utf8();
+    }
+    parts.push((start, &input[start..]));
+    Ok(parts)
+}
+
+// Like split_raw, but with the quotes and escapes removed.
+pub fn split_fields(input: &str, separator: char) -> Result<Vec<Field>, SplittingError> {
+    Ok(split_raw(input, separator)?
+        .into_iter()
+        .map(|(start, raw)| Field::new(raw, start))
+        .collect())
+}
+
+// Split at the first separator that is neither quoted nor escaped, returning the raw
+// parts on either side of it and the separator itself.
+pub fn split_once_raw(
+    input: &str,
+    is_separator: impl Fn(char) -> bool,
+) -> Result<Option<(&str, char, &str)>, SplittingError> {
+    Ok(separator_positions(input, is_separator)?
+        .first()
+        .map(|&position| {
+            let separator = input[position..].chars().next().unwrap();
+            (
+                &input[..position],
+                separator,
+                &input[position + separator.len_utf8()..],
+            )
+        }))
+}
+
+// Split a line into an item's name and its raw pattern, the last part of the line, along
+// with the offset of the pattern. A name may contain spaces, or be quoted to contain
+// anything at all.
+pub fn split_name_and_pattern(line: &str) -> Result<Option<(Field, usize, &str)>, SplittingError> {
+    let positions = separator_positions(line, char::is_whitespace)?;
+    let split = if line.starts_with('"') {
+        positions.first()
+    } else {
+        positions.last()
+    };
+    Ok(split.map(|&position| {
+        let name = line[..position].trim_end();
+        let pattern = line[position..].trim_start();
+        (Field::new(name, 0), line.len() - pattern.len(), pattern)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::tokenizer::{
+        escape, split_fields, split_name_and_pattern, split_once_raw, unquote, Field,
+    };
+    use crate::core::receipt::{InputSpan, SplittingError};
+
+    fn texts(fields: Vec<Field>) -> Vec<String> {
+        fields.into_iter().map(|field| field.text).collect()
+    }
+
+    #[test]
+    fn split_with_quotes_and_escapes() {
+        assert_eq!(
+            texts(split_fields(r#"300,"Smith, J",Bob"#, ',').unwrap()),
+            vec!["300", "Smith, J", "Bob"]
+        );
+        assert_eq!(
+            texts(split_fields(r#"Fish\, chips,Say \"cheese\",a\\"#, ',').unwrap()),
+            vec!["Fish, chips", r#"Say "cheese""#, r"a\"]
+        );
+        // Fields keep the byte range they were written in.
+        let fields = split_fields(r#"10,"A,l":2"#, ',').unwrap();
+        assert_eq!((fields[1].start, fields[1].end), (3, 10));
+        assert_eq!(texts(split_fields("", ',').unwrap()), vec![""]);
+
+        assert_eq!(
+            split_once_raw(r#""A=B":2=3"#, |c| c == '=' || c == ':').unwrap(),
+            Some((r#""A=B""#, ':', "2=3"))
+        );
+        let name = r#"Fish, "chips" \ peas=2"#;
+        assert_eq!(unquote(&escape(name, ',')), name);
+        // The delimiter of a locale is escaped too, so that it is not split on.
+        let name = "Smith; J";
+        assert_eq!(escape(name, ';'), r"Smith\; J");
+        let fields = split_fields(&format!("300;{};Bob", escape(name, ';')), ';').unwrap();
+        assert_eq!(texts(fields), vec!["300", name, "Bob"]);
+    }
+
+    #[test]
+    fn malformed_quotes_and_escapes() {
+        let error = split_fields(r#"300,"Smith,Bob"#, ',').unwrap_err();
+        assert!(matches!(error, SplittingError::InvalidArgument(_)));
+        assert_eq!(error.context().span, Some(InputSpan { start: 4, end: 14 }));
+
+        let error = split_fields(r"300,Bob\", ',').unwrap_err();
+        assert_eq!(error.context().span, Some(InputSpan { start: 7, end: 8 }));
+    }
+
+    #[test]
+    fn split_names_from_patterns() {
+        let (name, offset, pattern) = split_name_and_pattern("Red wine 50,Al").unwrap().unwrap();
+        assert_eq!(
+            (name.text.as_str(), offset, pattern),
+            ("Red wine", 9, "50,Al")
+        );
+
+        let (name, _, pattern) = split_name_and_pattern(r#""-20% promo, today" -20,"Al""#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(name.text, "-20% promo, today");
+        assert_eq!(pattern, r#"-20,"Al""#);
+
+        assert_eq!(split_name_and_pattern("Food").unwrap(), None);
+    }
+}
@@ -0,0 +1,880 @@
+use crate::cli::diagnostic;
+use crate::core::expression;
+use crate::core::history::{EditHistory, ReceiptEdit};
+use crate::core::locale::{Locale, LOCALES};
+use crate::core::receipt::{Receipt, SplittingError};
+use crate::core::validation::{Severity, ValidationIssue};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Table, Wrap};
+use ratatui::Frame;
+use rust_decimal::prelude::*;
+use std::process::ExitCode;
+
+// The screens of the terminal UI, one for each route of the web app.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Screen {
+    // The receipt's total, how amounts are written and the people sharing it.
+    CreateReceipt,
+    // The items, who shares each of them, and how much is left to balance.
+    Split,
+    // The splits, and how each person's share adds up.
+    DisplaySplits,
+}
+
+// The fields of the first screen, in the order Tab moves through them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CreateField {
+    Total,
+    Locale,
+    Currency,
+    Person(usize),
+    Submit,
+}
+
+// The fields of an item on the split screen, in the order Tab moves through them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ItemField {
+    Name,
+    Value,
+    Person(usize),
+}
+
+#[derive(Debug)]
+struct App {
+    screen: Screen,
+    // What was typed on the first screen.
+    total: String,
+    locale_idx: usize,
+    currency: String,
+    people: Vec<String>,
+    create_field: CreateField,
+    // The receipt being split, once the first screen has been submitted.
+    history: Option<EditHistory>,
+    item_idx: usize,
+    item_field: ItemField,
+    // What was typed in an item's value, which may be arithmetic, and what it evaluated
+    // to. It is shown while the item still has that value, as in the web app.
+    typed_value: Option<(usize, String, Decimal)>,
+    // The person whose column is selected on the splits, and whether their share is
+    // explained below them.
+    person_idx: usize,
+    explain: bool,
+    quit: bool,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        App {
+            screen: Screen::CreateReceipt,
+            total: String::new(),
+            locale_idx: 0,
+            currency: String::new(),
+            people: vec![String::new()],
+            create_field: CreateField::Total,
+            history: None,
+            item_idx: 0,
+            item_field: ItemField::Name,
+            typed_value: None,
+            person_idx: 0,
+            explain: false,
+            quit: false,
+        }
+    }
+}
+
+fn field_style(focused: bool) -> Style {
+    if focused {
+        Style::new().add_modifier(Modifier::REVERSED)
+    } else {
+        Style::new().add_modifier(Modifier::UNDERLINED)
+    }
+}
+
+fn issue_line(issue: &ValidationIssue) -> Line<'static> {
+    let colour = match issue.severity {
+        Severity::Error => Color::Red,
+        _ => Color::Yellow,
+    };
+    Line::styled(format!("  {}", issue.message), Style::new().fg(colour))
+}
+
+impl App {
+    fn with_receipt(receipt: Receipt) -> Self {
+        // The first screen shows the receipt as it was given, in case it is started again.
+        let locale = &receipt.locale;
+        let locale_idx = LOCALES
+            .iter()
+            .position(|name| {
+                name.parse::<Locale>().is_ok_and(|named| {
+                    (named.decimal, named.grouping) == (locale.decimal, locale.grouping)
+                })
+            })
+            .unwrap_or(0);
+        let mut app = App {
+            total: locale.format_amount(receipt.value),
+            locale_idx,
+            currency: locale.currency.clone().unwrap_or_default(),
+            people: receipt.shared_by.clone(),
+            ..App::default()
+        };
+        app.history = Some(EditHistory::new(receipt));
+        app.screen = Screen::Split;
+        app
+    }
+
+    fn receipt(&self) -> Option<&Receipt> {
+        self.history.as_ref().map(|history| history.receipt())
+    }
+
+    fn locale(&self) -> Locale {
+        let mut locale: Locale = LOCALES[self.locale_idx].parse().unwrap_or_default();
+        locale.currency = Some(self.currency.trim().to_string()).filter(|c| !c.is_empty());
+        locale
+    }
+
+    // The receipt the first screen describes, or None until it has a total and people.
+    fn new_receipt(&self) -> Option<Result<Receipt, SplittingError>> {
+        let locale = self.locale();
+        let value = expression::evaluate_in(&self.total, &locale).ok()?;
+        let people: Vec<&str> = self
+            .people
+            .iter()
+            .map(|person| person.trim())
+            .filter(|person| !person.is_empty())
+            .collect();
+        if people.is_empty() {
+            return None;
+        }
+        Some(Receipt::new(value, people).map(|mut receipt| {
+            receipt.locale = locale;
+            receipt
+        }))
+    }
+
+    fn create_fields(&self) -> Vec<CreateField> {
+        let mut fields = vec![
+            CreateField::Total,
+            CreateField::Locale,
+            CreateField::Currency,
+        ];
+        fields.extend((0..self.people.len()).map(CreateField::Person));
+        fields.push(CreateField::Submit);
+        fields
+    }
+
+    fn item_fields(&self) -> Vec<ItemField> {
+        let people = self.receipt().map_or(0, |receipt| receipt.shared_by.len());
+        let mut fields = vec![ItemField::Name, ItemField::Value];
+        fields.extend((0..people).map(ItemField::Person));
+        fields
+    }
+
+    // The value of an item as it is shown: what was typed, or else the amount.
+    fn item_value_text(&self, item_idx: usize) -> String {
+        let Some(receipt) = self.receipt() else {
+            return String::new();
+        };
+        let value = receipt.items[item_idx].value;
+        match &self.typed_value {
+            Some((idx, typed, typed_value)) if *idx == item_idx && *typed_value == value => {
+                typed.clone()
+            }
+            _ if !value.is_zero() => receipt.locale.format_amount(value),
+            _ => String::new(),
+        }
+    }
+
+    fn can_show_splits(&self) -> bool {
+        self.receipt().is_some_and(|receipt| {
+            !receipt.items.is_empty()
+                && !receipt.has_validation_errors()
+                && receipt.calculate_splits().is_ok()
+        })
+    }
+
+    fn edit(&mut self, edit: ReceiptEdit, amend: bool) {
+        if let Some(history) = self.history.as_mut() {
+            // Edits that would make the receipt invalid, such as leaving an item with no
+            // one sharing it, are ignored as they are in the web app.
+            let _ = if amend {
+                history.amend(edit)
+            } else {
+                history.apply(edit)
+            };
+        }
+        let items = self.receipt().map_or(0, |receipt| receipt.items.len());
+        self.item_idx = self.item_idx.min(items.saturating_sub(1));
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        if control && matches!(key.code, KeyCode::Char('c') | KeyCode::Char('q')) {
+            self.quit = true;
+            return;
+        }
+        match self.screen {
+            Screen::CreateReceipt => self.handle_create_key(key),
+            Screen::Split => self.handle_split_key(key, control),
+            Screen::DisplaySplits => self.handle_display_key(key),
+        }
+    }
+
+    fn handle_create_key(&mut self, key: KeyEvent) {
+        let fields = self.create_fields();
+        let position = fields
+            .iter()
+            .position(|field| *field == self.create_field)
+            .unwrap_or(0);
+        match (key.code, self.create_field) {
+            (KeyCode::Esc, _) => self.quit = true,
+            (KeyCode::Tab | KeyCode::Down, _) => {
+                self.create_field = fields[(position + 1) % fields.len()];
+            }
+            (KeyCode::BackTab | KeyCode::Up, _) => {
+                self.create_field = fields[(position + fields.len() - 1) % fields.len()];
+            }
+            (KeyCode::Left, CreateField::Locale) => {
+                self.locale_idx = (self.locale_idx + LOCALES.len() - 1) % LOCALES.len();
+            }
+            (KeyCode::Right | KeyCode::Char(' '), CreateField::Locale) => {
+                self.locale_idx = (self.locale_idx + 1) % LOCALES.len();
+            }
+            // Enter in a person's name adds another person after them.
+            (KeyCode::Enter, CreateField::Person(idx)) => {
+                self.people.insert(idx + 1, String::new());
+                self.create_field = CreateField::Person(idx + 1);
+            }
+            (KeyCode::Enter, CreateField::Submit) => {
+                if let Some(Ok(receipt)) = self.new_receipt() {
+                    self.history = Some(EditHistory::new(receipt));
+                    self.item_idx = 0;
+                    self.item_field = ItemField::Name;
+                    self.screen = Screen::Split;
+                }
+            }
+            (KeyCode::Enter, _) => self.create_field = fields[position + 1],
+            // Backspace in an empty name removes that person.
+            (KeyCode::Backspace, CreateField::Person(idx))
+                if self.people[idx].is_empty() && self.people.len() > 1 =>
+            {
+                self.people.remove(idx);
+                self.create_field = CreateField::Person(idx.saturating_sub(1));
+            }
+            (KeyCode::Backspace, field) => {
+                if let Some(text) = self.create_text(field) {
+                    text.pop();
+                }
+            }
+            (KeyCode::Char(c), field) => {
+                if let Some(text) = self.create_text(field) {
+                    text.push(c);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn create_text(&mut self, field: CreateField) -> Option<&mut String> {
+        match field {
+            CreateField::Total => Some(&mut self.total),
+            CreateField::Currency => Some(&mut self.currency),
+            CreateField::Person(idx) => self.people.get_mut(idx),
+            CreateField::Locale | CreateField::Submit => None,
+        }
+    }
+
+    fn handle_split_key(&mut self, key: KeyEvent, control: bool) {
+        let Some(receipt) = self.receipt() else {
+            return;
+        };
+        let items = receipt.items.len();
+        let people = receipt.shared_by.clone();
+        let item_idx = self.item_idx;
+        if control {
+            match key.code {
+                KeyCode::Char('n') => {
+                    self.edit(
+                        ReceiptEdit::AddItem {
+                            value: Decimal::ZERO,
+                            name: format!("Item {}", items + 1),
+                            shared_by: people,
+                            share_ratio: None,
+                        },
+                        false,
+                    );
+                    self.item_idx = items;
+                    self.item_field = ItemField::Name;
+                }
+                KeyCode::Char('d') if items > 0 => {
+                    self.edit(ReceiptEdit::RemoveItem { item_idx }, false)
+                }
+                KeyCode::Char('z') => {
+                    if let Some(history) = self.history.as_mut() {
+                        let _ = history.undo();
+                    }
+                    self.edit_done();
+                }
+                KeyCode::Char('y') => {
+                    if let Some(history) = self.history.as_mut() {
+                        let _ = history.redo();
+                    }
+                    self.edit_done();
+                }
+                KeyCode::Char('s') if self.can_show_splits() => {
+                    self.person_idx = 0;
+                    self.explain = false;
+                    self.screen = Screen::DisplaySplits;
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        let fields = self.item_fields();
+        let position = fields
+            .iter()
+            .position(|field| *field == self.item_field)
+            .unwrap_or(0);
+        match (key.code, self.item_field) {
+            (KeyCode::Esc, _) => self.screen = Screen::CreateReceipt,
+            (KeyCode::Up, _) => self.item_idx = item_idx.saturating_sub(1),
+            (KeyCode::Down, _) => self.item_idx = (item_idx + 1).min(items.saturating_sub(1)),
+            (KeyCode::Tab | KeyCode::Right, _) => {
+                self.item_field = fields[(position + 1) % fields.len()];
+            }
+            (KeyCode::BackTab | KeyCode::Left, _) => {
+                self.item_field = fields[(position + fields.len() - 1) % fields.len()];
+            }
+            _ if items == 0 => {}
+            (KeyCode::Char(' ') | KeyCode::Enter, ItemField::Person(idx)) => self.edit(
+                ReceiptEdit::ToggleSharer {
+                    item_idx,
+                    person: people[idx].clone(),
+                },
+                false,
+            ),
+            (KeyCode::Backspace | KeyCode::Char(_), ItemField::Name) => {
+                let mut name = receipt.items[item_idx].name.clone();
+                match key.code {
+                    KeyCode::Char(c) => name.push(c),
+                    _ => {
+                        name.pop();
+                    }
+                }
+                self.edit(ReceiptEdit::EditItemName { item_idx, name }, true);
+            }
+            (KeyCode::Backspace | KeyCode::Char(_), ItemField::Value) => {
+                let mut typed = self.item_value_text(item_idx);
+                match key.code {
+                    KeyCode::Char(c) => typed.push(c),
+                    _ => {
+                        typed.pop();
+                    }
+                }
+                let value =
+                    expression::evaluate_in(&typed, &receipt.locale).unwrap_or(Decimal::ZERO);
+                self.typed_value = Some((item_idx, typed, value));
+                self.edit(ReceiptEdit::EditItemValue { item_idx, value }, true);
+            }
+            _ => {}
+        }
+    }
+
+    // After an undo or a redo, the selected item may no longer exist.
+    fn edit_done(&mut self) {
+        let items = self.receipt().map_or(0, |receipt| receipt.items.len());
+        self.item_idx = self.item_idx.min(items.saturating_sub(1));
+    }
+
+    fn handle_display_key(&mut self, key: KeyEvent) {
+        let people = self.receipt().map_or(1, |receipt| receipt.shared_by.len());
+        match key.code {
+            KeyCode::Esc | KeyCode::Backspace => self.screen = Screen::Split,
+            KeyCode::Left | KeyCode::Up => self.person_idx = self.person_idx.saturating_sub(1),
+            KeyCode::Right | KeyCode::Down => {
+                self.person_idx = (self.person_idx + 1).min(people - 1)
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => self.explain = !self.explain,
+            _ => {}
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(4),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let (title, subtitle, hints) = match self.screen {
+            Screen::CreateReceipt => (
+                "BorrowChecker".to_string(),
+                "A utility to determine how who owes what".to_string(),
+                "Tab next field  ←→ amounts  Enter add person or submit  Esc quit",
+            ),
+            Screen::Split => {
+                let (title, subtitle) = self.balance_title();
+                (
+                    title,
+                    subtitle,
+                    "↑↓ item  Tab field  Space share  ^N add  ^D remove  ^Z undo  ^Y redo  ^S splits",
+                )
+            }
+            Screen::DisplaySplits => (
+                "Here's your split!".to_string(),
+                "Balance leftover is distributed proportionally.".to_string(),
+                "←→ person  Enter explain their share  Esc back  ^Q quit",
+            ),
+        };
+        let title_style = match self.screen {
+            Screen::Split => self.balance_style(),
+            _ => Style::new().add_modifier(Modifier::BOLD),
+        };
+        frame.render_widget(
+            Paragraph::new(vec![Line::styled(title, title_style), Line::raw(subtitle)])
+                .centered()
+                .block(Block::new().borders(Borders::BOTTOM)),
+            header,
+        );
+        match self.screen {
+            Screen::CreateReceipt => self.draw_create(frame, body),
+            Screen::Split => self.draw_split(frame, body),
+            Screen::DisplaySplits => self.draw_splits(frame, body),
+        }
+        frame.render_widget(
+            Paragraph::new(hints).style(Style::new().add_modifier(Modifier::DIM)),
+            footer,
+        );
+    }
+
+    fn draw_create(&self, frame: &mut Frame, area: Rect) {
+        let example = dec![1234.5];
+        let locale = self.locale();
+        let focused = |field: CreateField| field_style(self.create_field == field);
+        let total_style = match expression::evaluate_in(&self.total, &locale) {
+            Err(_) if !self.total.is_empty() => focused(CreateField::Total).fg(Color::Red),
+            _ => focused(CreateField::Total),
+        };
+        let receipt = self.new_receipt();
+        let invalid_person = match &receipt {
+            Some(Err(error)) => error.context().person.clone(),
+            _ => None,
+        };
+
+        let mut lines = vec![
+            Line::from(vec![
+                Span::raw("Total     "),
+                Span::styled(format!("{:<16}", self.total), total_style),
+            ]),
+            Line::from(vec![
+                Span::raw("Amounts   "),
+                Span::styled(
+                    format!("‹ {} ›", locale.format_amount(example)),
+                    focused(CreateField::Locale),
+                ),
+            ]),
+            Line::from(vec![
+                Span::raw("Currency  "),
+                Span::styled(
+                    format!("{:<16}", self.currency),
+                    focused(CreateField::Currency),
+                ),
+            ]),
+            Line::raw(""),
+        ];
+        for (idx, person) in self.people.iter().enumerate() {
+            let mut style = focused(CreateField::Person(idx));
+            if invalid_person.as_deref() == Some(person.trim()) {
+                style = style.fg(Color::Red);
+            }
+            lines.push(Line::from(vec![
+                Span::raw(format!("Person {:<3}", idx + 1)),
+                Span::styled(format!("{:<16}", person), style),
+            ]));
+        }
+        lines.push(Line::raw(""));
+        lines.push(match &receipt {
+            Some(Ok(_)) => {
+                Line::styled("[ Submit ]", focused(CreateField::Submit).fg(Color::Green))
+            }
+            Some(Err(error)) => Line::styled(error.to_string(), Style::new().fg(Color::Red)),
+            None => Line::raw("Provide a total amount and at least two people to start splitting!"),
+        });
+        frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), area);
+    }
+
+    fn balance_title(&self) -> (String, String) {
+        let Some(receipt) = self.receipt() else {
+            return (String::new(), String::new());
+        };
+        let (_, balance) = receipt.get_itemized_total_and_leftover();
+        let amount = receipt.locale.format_money(balance);
+        if balance > Decimal::ZERO {
+            (format!("+{}", amount), "left to balance".into())
+        } else if balance < Decimal::ZERO {
+            (
+                format!("Remaining: {}", amount),
+                "Item total exceeds receipt total.".into(),
+            )
+        } else {
+            (
+                "0".into(),
+                "Perfectly balanced, as all things should be.".into(),
+            )
+        }
+    }
+
+    fn balance_style(&self) -> Style {
+        let balance = self
+            .receipt()
+            .map(|receipt| receipt.get_itemized_total_and_leftover().1)
+            .unwrap_or_default();
+        let style = Style::new().add_modifier(Modifier::BOLD);
+        match balance.cmp(&Decimal::ZERO) {
+            std::cmp::Ordering::Less => style.fg(Color::Red),
+            std::cmp::Ordering::Equal => style.fg(Color::Blue),
+            std::cmp::Ordering::Greater => style,
+        }
+    }
+
+    fn draw_split(&self, frame: &mut Frame, area: Rect) {
+        let Some(receipt) = self.receipt() else {
+            return;
+        };
+        let issues = receipt.validate();
+        let mut lines: Vec<Line> = Vec::new();
+        let mut selected_line = 0;
+        for (item_idx, item) in receipt.items.iter().enumerate() {
+            let selected = item_idx == self.item_idx;
+            let focused = |field: ItemField| field_style(selected && self.item_field == field);
+            if selected {
+                selected_line = lines.len();
+            }
+            let marker = if selected { "▶ " } else { "  " };
+            lines.push(Line::from(vec![
+                Span::raw(marker),
+                Span::styled(format!("{:<24}", item.name), focused(ItemField::Name)),
+                Span::raw(" "),
+                Span::styled(
+                    format!("{:>12}", self.item_value_text(item_idx)),
+                    focused(ItemField::Value),
+                ),
+            ]));
+            let mut people: Vec<Span> = vec![Span::raw("  ")];
+            for (person_idx, person) in receipt.shared_by.iter().enumerate() {
+                let mut style = if item.shared_by.contains(person) {
+                    Style::new().fg(Color::Black).bg(Color::Green)
+                } else {
+                    Style::new().fg(Color::Green)
+                };
+                if selected && self.item_field == ItemField::Person(person_idx) {
+                    style = style.add_modifier(Modifier::REVERSED | Modifier::BOLD);
+                }
+                people.push(Span::styled(format!(" {} ", person), style));
+                people.push(Span::raw(" "));
+            }
+            lines.push(Line::from(people));
+            lines.extend(
+                issues
+                    .iter()
+                    .filter(|issue| issue.item_idx == Some(item_idx))
+                    .map(issue_line),
+            );
+            lines.push(Line::raw(""));
+        }
+        if receipt.items.is_empty() {
+            lines.push(Line::raw("There are no items yet. Add one with Ctrl+N."));
+        } else {
+            lines.extend(
+                issues
+                    .iter()
+                    .filter(|issue| issue.item_idx.is_none())
+                    .map(issue_line),
+            );
+        }
+
+        // Keep the selected item, and the two lines below its name, on the screen.
+        let scroll = (selected_line + 3).saturating_sub(area.height as usize);
+        frame.render_widget(Paragraph::new(lines).scroll((scroll as u16, 0)), area);
+    }
+
+    fn draw_splits(&self, frame: &mut Frame, area: Rect) {
+        let Some(receipt) = self.receipt() else {
+            return;
+        };
+        let splits = receipt
+            .calculate_splits()
+            .and_then(|(mut names, mut splits)| {
+                let (payment_names, payment_splits) = receipt.calculate_payment_splits()?;
+                names.extend(payment_names);
+                splits.extend(payment_splits);
+                Ok((names, splits))
+            });
+        let (item_names, item_splits) = match splits {
+            Ok(splits) => splits,
+            Err(error) => {
+                let message = diagnostic::render_error(&error, None);
+                frame.render_widget(
+                    Paragraph::new(message).style(Style::new().fg(Color::Red)),
+                    area,
+                );
+                return;
+            }
+        };
+
+        let mut header = vec!["Item Name".to_string()];
+        header.extend(receipt.shared_by.iter().cloned());
+        header.push("Total".into());
+        let rows: Vec<Vec<String>> = item_names
+            .iter()
+            .zip(item_splits.iter())
+            .map(|(name, splits)| {
+                let mut row = vec![name.to_string()];
+                row.extend(splits.iter().map(|x| receipt.locale.format_money(*x)));
+                row
+            })
+            .collect();
+        let widths: Vec<Constraint> = (0..header.len())
+            .map(|column| {
+                let width = rows
+                    .iter()
+                    .map(|row| row[column].chars().count())
+                    .chain([header[column].chars().count()])
+                    .max()
+                    .unwrap_or(0);
+                Constraint::Length(width as u16)
+            })
+            .collect();
+        let column_style = |column: usize| {
+            if column == self.person_idx + 1 {
+                Style::new().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::new()
+            }
+        };
+        let header = Row::new(
+            header
+                .into_iter()
+                .enumerate()
+                .map(|(column, name)| ratatui::text::Text::styled(name, column_style(column))),
+        )
+        .style(Style::new().add_modifier(Modifier::BOLD));
+        let table_height = rows.len() as u16 + 1;
+        let rows = rows.into_iter().map(|row| {
+            Row::new(row.into_iter().enumerate().map(|(column, value)| {
+                let text = ratatui::text::Text::styled(value, column_style(column));
+                match column {
+                    0 => text,
+                    _ => text.right_aligned(),
+                }
+            }))
+        });
+
+        let [table_area, explanation_area] =
+            Layout::vertical([Constraint::Length(table_height + 1), Constraint::Min(0)])
+                .areas(area);
+        frame.render_widget(
+            Table::new(rows, widths).header(header).column_spacing(2),
+            table_area,
+        );
+
+        let person = &receipt.shared_by[self.person_idx.min(receipt.shared_by.len() - 1)];
+        let lines: Vec<Line> = match receipt.explain(person) {
+            Ok(explanation) if self.explain => {
+                let mut lines = vec![Line::styled(
+                    format!("How {}'s share adds up", explanation.person),
+                    Style::new().add_modifier(Modifier::BOLD),
+                )];
+                lines.extend(
+                    explanation
+                        .describe(&receipt.locale)
+                        .into_iter()
+                        .map(|line| Line::raw(format!("  {}", line))),
+                );
+                lines
+            }
+            Ok(explanation) => vec![Line::raw(format!(
+                "▸ How {}'s share adds up",
+                explanation.person
+            ))],
+            Err(error) => vec![Line::styled(error.to_string(), Style::new().fg(Color::Red))],
+        };
+        frame.render_widget(Paragraph::new(lines), explanation_area);
+    }
+}
+
+// Split a receipt in a full-screen terminal UI, optionally starting from a receipt
+// rather than its first screen.
+pub fn run(receipt: Option<Receipt>) -> ExitCode {
+    let mut app = match receipt {
+        None => App::default(),
+        Some(receipt) => App::with_receipt(receipt),
+    };
+    let mut terminal = ratatui::init();
+    let result = loop {
+        if let Err(error) = terminal.draw(|frame| app.draw(frame)) {
+            break Err(error);
+        }
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => app.handle_key(key),
+            Ok(_) => {}
+            Err(error) => break Err(error),
+        }
+        if app.quit {
+            break Ok(());
+        }
+    };
+    ratatui::restore();
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: the terminal UI stopped: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::arg_parser;
+    use crate::cli::tui::{App, CreateField, ItemField, Screen};
+    use crate::core::locale::Locale;
+    use crate::utils;
+    use ratatui::backend::TestBackend;
+    use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::Terminal;
+    use rust_decimal::prelude::*;
+
+    fn press(app: &mut App, keys: &str) {
+        for c in keys.chars() {
+            app.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+    }
+
+    fn press_code(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    fn press_control(app: &mut App, c: char) {
+        app.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL));
+    }
+
+    fn screen_text(app: &App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn split_a_receipt_through_every_screen() {
+        let mut app = App::default();
+        press(&mut app, "100");
+        press_code(&mut app, KeyCode::Tab);
+        press_code(&mut app, KeyCode::Tab);
+        press_code(&mut app, KeyCode::Tab);
+        assert_eq!(app.create_field, CreateField::Person(0));
+        press(&mut app, "Alice");
+        press_code(&mut app, KeyCode::Enter);
+        press(&mut app, "Bob");
+        press_code(&mut app, KeyCode::Tab);
+        assert!(screen_text(&app).contains("[ Submit ]"));
+        press_code(&mut app, KeyCode::Enter);
+        assert_eq!(app.screen, Screen::Split);
+
+        // Add an item of 60, and take Bob off it.
+        press_control(&mut app, 'n');
+        press_code(&mut app, KeyCode::Tab);
+        assert_eq!(app.item_field, ItemField::Value);
+        press(&mut app, "2*30");
+        press_code(&mut app, KeyCode::Tab);
+        press_code(&mut app, KeyCode::Tab);
+        press(&mut app, " ");
+        let receipt = app.receipt().unwrap();
+        assert_eq!(receipt.items[0].value, dec![60]);
+        assert_eq!(receipt.items[0].shared_by, vec!["Alice".to_string()]);
+        let text = screen_text(&app);
+        assert!(text.contains("+40.00"), "{}", text);
+        assert!(text.contains("2*30"), "{}", text);
+
+        // Undoing the toggle shares the item with Bob again.
+        press_control(&mut app, 'z');
+        assert_eq!(app.receipt().unwrap().items[0].shared_by.len(), 2);
+
+        press_control(&mut app, 's');
+        assert_eq!(app.screen, Screen::DisplaySplits);
+        press_code(&mut app, KeyCode::Right);
+        press_code(&mut app, KeyCode::Enter);
+        let text = screen_text(&app);
+        assert!(text.contains("<leftover>"), "{}", text);
+        assert!(text.contains("How Bob's share adds up"), "{}", text);
+        assert!(text.contains("<total>: 50.00"), "{}", text);
+    }
+
+    #[test]
+    fn point_out_who_cannot_share_a_receipt() {
+        let mut app = App::default();
+        press(&mut app, "100");
+        app.create_field = CreateField::Person(0);
+        press(&mut app, "Alice");
+        press_code(&mut app, KeyCode::Enter);
+        press(&mut app, "Alice");
+        app.create_field = CreateField::Submit;
+        press_code(&mut app, KeyCode::Enter);
+        assert_eq!(app.screen, Screen::CreateReceipt);
+        assert!(screen_text(&app).contains("duplicated"));
+    }
+
+    #[test]
+    fn start_from_a_receipt_in_its_locale() {
+        let args = utils::strs_to_strings(vec!["--tui", "1.234,50;Alice;Bob", "--locale", "de"]);
+        let receipt = arg_parser::parse_starting_receipt(&args, Locale::default())
+            .unwrap()
+            .unwrap();
+        let mut app = App::with_receipt(receipt);
+        assert_eq!(app.screen, Screen::Split);
+        assert!(
+            screen_text(&app).contains("1.234,50"),
+            "{}",
+            screen_text(&app)
+        );
+
+        // Going back keeps the locale, so the same receipt is started again.
+        press_code(&mut app, KeyCode::Esc);
+        app.create_field = CreateField::Submit;
+        press_code(&mut app, KeyCode::Enter);
+        let receipt = app.receipt().unwrap();
+        assert_eq!(receipt.value, Decimal::new(123450, 2));
+        assert_eq!(receipt.locale.decimal, ',');
+    }
+
+    #[test]
+    fn show_discounts_as_amounts() {
+        let mut app = App::default();
+        press(&mut app, "100");
+        app.create_field = CreateField::Person(0);
+        press(&mut app, "Alice");
+        press_code(&mut app, KeyCode::Enter);
+        press(&mut app, "Bob");
+        app.create_field = CreateField::Submit;
+        press_code(&mut app, KeyCode::Enter);
+        press_control(&mut app, 'n');
+        press_code(&mut app, KeyCode::Tab);
+        press(&mut app, "-20");
+        assert_eq!(app.receipt().unwrap().items[0].value, dec![-20]);
+
+        // Once what was typed is gone, the amount is shown, and only zero is left blank.
+        app.typed_value = None;
+        assert_eq!(app.item_value_text(0), "-20.00");
+        press_control(&mut app, 'n');
+        assert_eq!(app.item_value_text(1), "");
+    }
+}
@@ -1,11 +1,41 @@
-use crate::core::receipt::SplittingError;
+use crate::cli::tokenizer;
+use crate::core::expression;
+use crate::core::locale::Locale;
+use crate::core::receipt::{ErrorContext, SplittingError};
 
-pub fn split_by_comma(
-    input_str: &str,
-    error_message: &str,
-) -> Result<(String, String), SplittingError> {
-    input_str
-        .split_once(",")
-        .map(|(value, other)| (value.to_string(), other.to_string()))
-        .ok_or_else(|| SplittingError::DecimalParsingError(error_message.to_string()))
+// Split at the first delimiter that is neither quoted nor escaped, keeping the quotes
+// and escapes on either side. The input is expected to have the given pattern, written
+// with commas.
+pub fn split_by_delimiter<'a>(
+    input_str: &'a str,
+    delimiter: char,
+    pattern: &str,
+) -> Result<(&'a str, &'a str), SplittingError> {
+    tokenizer::split_once_raw(input_str, |c| c == delimiter)?
+        .map(|(value, _, other)| (value, other))
+        .ok_or_else(|| {
+            let pattern = pattern.replace(',', &delimiter.to_string());
+            SplittingError::InvalidArgument(
+                ErrorContext::new(format!(
+                    "{} must have pattern '{}', but it has no '{}'.",
+                    input_str, pattern, delimiter
+                ))
+                .with_value(input_str)
+                .with_span(0, input_str.len())
+                .with_help(format!(
+                    "separate its parts with '{}', as in '{}'",
+                    delimiter, pattern
+                )),
+            )
+        })
+}
+
+// Parse an amount written in a locale, which may be arithmetic such as '3*4.50',
+// recording the offending value and where it is in the input if it fails to parse.
+pub fn parse_decimal(
+    value: &str,
+    offset: usize,
+    locale: &Locale,
+) -> Result<rust_decimal::Decimal, SplittingError> {
+    expression::evaluate_in(value, locale).map_err(|e| e.shift_span(offset))
 }
@@ -0,0 +1,106 @@
+use crate::core::receipt::{ErrorContext, Person, Receipt, SplittingError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Nicknames for people, kept across receipts (such as for a group that often splits
+// bills), so that 'Ally' always means Alice. Aliases seed a receipt's abbreviations,
+// and take priority over what the abbreviation would otherwise match.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AliasTable {
+    aliases: BTreeMap<String, Person>,
+}
+
+impl AliasTable {
+    pub fn new() -> Self {
+        AliasTable::default()
+    }
+
+    pub fn insert(&mut self, alias: &str, person: &str) -> Result<(), SplittingError> {
+        if alias.is_empty() || person.is_empty() {
+            return Err(SplittingError::InvalidArgument(
+                ErrorContext::new(format!(
+                    "An alias must have pattern 'Alias=Person', but you have {}={}",
+                    alias, person
+                ))
+                .with_value(format!("{}={}", alias, person)),
+            ));
+        }
+        self.aliases.insert(alias.to_string(), person.to_string());
+        Ok(())
+    }
+
+    // Parse and add an 'Alias=Person' pattern.
+    pub fn parse_insert(&mut self, pattern: &str) -> Result<(), SplittingError> {
+        match pattern.split_once("=") {
+            Some((alias, person)) => self.insert(alias.trim(), person.trim()),
+            None => Err(SplittingError::InvalidArgument(
+                ErrorContext::new(format!(
+                    "An alias must have pattern 'Alias=Person', but you have {}",
+                    pattern
+                ))
+                .with_value(pattern)
+                .with_span(0, pattern.len()),
+            )),
+        }
+    }
+
+    pub fn get(&self, alias: &str) -> Option<&Person> {
+        self.aliases.get(alias)
+    }
+
+    pub fn remove(&mut self, alias: &str) -> Option<Person> {
+        self.aliases.remove(alias)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Person)> {
+        self.aliases.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+
+    pub fn extend(&mut self, other: &AliasTable) {
+        self.aliases.extend(
+            other
+                .iter()
+                .map(|(alias, person)| (alias.clone(), person.clone())),
+        );
+    }
+}
+
+impl Receipt {
+    // Map each alias of a person sharing the receipt to them. Aliases of anyone else
+    // are ignored, as the table may cover more people than this receipt.
+    pub fn seed_aliases(&mut self, aliases: &AliasTable) {
+        for (alias, person) in aliases.iter() {
+            if self.shared_by.contains(person) {
+                self.mapped_abbreviations
+                    .insert(alias.clone(), person.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::alias::AliasTable;
+    use crate::core::receipt::Receipt;
+    use rust_decimal::prelude::*;
+
+    #[test]
+    fn seed_receipt_with_aliases() {
+        let mut aliases = AliasTable::new();
+        aliases.parse_insert("Ally=Alice").unwrap();
+        aliases.parse_insert("Bobby = Bob").unwrap();
+        aliases.parse_insert("Max=Maxine").unwrap();
+        assert!(aliases.parse_insert("Ally").is_err());
+        assert!(aliases.parse_insert("=Alice").is_err());
+
+        let mut receipt = Receipt::new(dec![100], vec!["Alice", "Bob"]).unwrap();
+        receipt.seed_aliases(&aliases);
+        assert_eq!(receipt.mapped_abbreviations.len(), 2);
+        assert_eq!(receipt.mapped_abbreviations["Bobby"], "Bob");
+    }
+}
@@ -0,0 +1,272 @@
+use crate::core::receipt::Person;
+use std::error::Error;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+// Byte range within a single input (a command line argument, or a line of a file)
+// that an error was found in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InputSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+// Everything known about where an error happened, alongside the friendly message.
+// Fields are filled in as the error travels up to the caller, who knows more about
+// the input than the function that failed. They are boxed, so that a Result with a
+// SplittingError is hardly larger than its value.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ErrorContext(Box<ErrorDetails>);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ErrorDetails {
+    pub message: String,
+    pub item_idx: Option<usize>,
+    pub item_name: Option<String>,
+    pub person: Option<Person>,
+    // The offending value, as it was provided.
+    pub value: Option<String>,
+    // Index of the argument (or line) the offending value was provided in.
+    pub argument: Option<usize>,
+    pub span: Option<InputSpan>,
+    // File the input was read from, if any. The argument is then the line of the file.
+    pub file: Option<String>,
+    // People the offending value could have meant, when it is ambiguous.
+    pub candidates: Vec<Person>,
+    // A suggestion of how to fix the input.
+    pub help: Option<String>,
+}
+
+impl ErrorContext {
+    pub fn new(message: impl Into<String>) -> Self {
+        ErrorContext(Box::new(ErrorDetails {
+            message: message.into(),
+            ..Default::default()
+        }))
+    }
+
+    pub fn with_item(mut self, item_idx: usize, item_name: &str) -> Self {
+        self.item_idx = Some(item_idx);
+        self.item_name = Some(item_name.to_string());
+        self
+    }
+
+    pub fn with_person(mut self, person: &str) -> Self {
+        self.person = Some(person.to_string());
+        self
+    }
+
+    pub fn with_value(mut self, value: impl ToString) -> Self {
+        self.value = Some(value.to_string());
+        self
+    }
+
+    pub fn with_span(mut self, start: usize, end: usize) -> Self {
+        self.span = Some(InputSpan { start, end });
+        self
+    }
+
+    pub fn with_candidates(mut self, candidates: Vec<Person>) -> Self {
+        self.candidates = candidates;
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+}
+
+impl Deref for ErrorContext {
+    type Target = ErrorDetails;
+
+    fn deref(&self) -> &ErrorDetails {
+        &self.0
+    }
+}
+
+impl DerefMut for ErrorContext {
+    fn deref_mut(&mut self) -> &mut ErrorDetails {
+        &mut self.0
+    }
+}
+
+impl From<&str> for ErrorContext {
+    fn from(message: &str) -> Self {
+        ErrorContext::new(message)
+    }
+}
+
+impl From<String> for ErrorContext {
+    fn from(message: String) -> Self {
+        ErrorContext::new(message)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SplittingError {
+    DuplicatePeopleError(ErrorContext),
+    NotEnoughPeopleError(ErrorContext),
+    InvalidShareConfiguration(ErrorContext),
+    InvalidFieldError(ErrorContext),
+    InvalidAbbreviation(ErrorContext),
+    AmbiguousAbbreviation(ErrorContext),
+    InternalError(ErrorContext),
+    ItemTotalExceedsReceiptTotal(ErrorContext),
+    DecimalParsingError(ErrorContext),
+    InvalidArgument(ErrorContext),
+    PaymentTotalMismatch(ErrorContext),
+    // A receipt file could not be read or a result could not be written.
+    FileError(ErrorContext),
+}
+
+impl SplittingError {
+    pub fn context(&self) -> &ErrorContext {
+        match self {
+            Self::DuplicatePeopleError(context)
+            | Self::NotEnoughPeopleError(context)
+            | Self::InvalidShareConfiguration(context)
+            | Self::InvalidFieldError(context)
+            | Self::InvalidAbbreviation(context)
+            | Self::AmbiguousAbbreviation(context)
+            | Self::InternalError(context)
+            | Self::ItemTotalExceedsReceiptTotal(context)
+            | Self::DecimalParsingError(context)
+            | Self::InvalidArgument(context)
+            | Self::PaymentTotalMismatch(context)
+            | Self::FileError(context) => context,
+        }
+    }
+
+    fn context_mut(&mut self) -> &mut ErrorContext {
+        match self {
+            Self::DuplicatePeopleError(context)
+            | Self::NotEnoughPeopleError(context)
+            | Self::InvalidShareConfiguration(context)
+            | Self::InvalidFieldError(context)
+            | Self::InvalidAbbreviation(context)
+            | Self::AmbiguousAbbreviation(context)
+            | Self::InternalError(context)
+            | Self::ItemTotalExceedsReceiptTotal(context)
+            | Self::DecimalParsingError(context)
+            | Self::InvalidArgument(context)
+            | Self::PaymentTotalMismatch(context)
+            | Self::FileError(context) => context,
+        }
+    }
+
+    // Stable, machine-readable identifier of the kind of error. These must not change
+    // once released, as scripts and frontends match on them.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::DuplicatePeopleError(_) => "duplicate-people",
+            Self::NotEnoughPeopleError(_) => "not-enough-people",
+            Self::InvalidShareConfiguration(_) => "invalid-share-configuration",
+            Self::InvalidFieldError(_) => "invalid-field",
+            Self::InvalidAbbreviation(_) => "invalid-abbreviation",
+            Self::AmbiguousAbbreviation(_) => "ambiguous-abbreviation",
+            Self::InternalError(_) => "internal",
+            Self::ItemTotalExceedsReceiptTotal(_) => "item-total-exceeds-receipt-total",
+            Self::DecimalParsingError(_) => "decimal-parsing",
+            Self::InvalidArgument(_) => "invalid-argument",
+            Self::PaymentTotalMismatch(_) => "payment-total-mismatch",
+            Self::FileError(_) => "file",
+        }
+    }
+
+    // Exit code of the command-line app for the kind of error, distinct for each kind so
+    // that scripts need not read the message. Like the codes above, these must not change
+    // once released. 2 is also used for any other misuse of the command line, and 1 is
+    // left for failures that are not errors in the input.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::InvalidArgument(_) => 2,
+            Self::DuplicatePeopleError(_) => 3,
+            Self::NotEnoughPeopleError(_) => 4,
+            Self::InvalidShareConfiguration(_) => 5,
+            Self::InvalidFieldError(_) => 6,
+            Self::InvalidAbbreviation(_) => 7,
+            Self::AmbiguousAbbreviation(_) => 8,
+            Self::ItemTotalExceedsReceiptTotal(_) => 9,
+            Self::DecimalParsingError(_) => 10,
+            Self::PaymentTotalMismatch(_) => 11,
+            Self::FileError(_) => 12,
+            Self::InternalError(_) => 70,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.context().message
+    }
+
+    // Record the item the error is about, unless something more specific already has.
+    pub fn with_item(mut self, item_idx: usize, item_name: &str) -> Self {
+        let context = self.context_mut();
+        if context.item_idx.is_none() {
+            context.item_idx = Some(item_idx);
+            context.item_name = Some(item_name.to_string());
+        }
+        self
+    }
+
+    // Record the argument (or line) the error was found in.
+    pub fn in_argument(mut self, argument: usize) -> Self {
+        let context = self.context_mut();
+        if context.argument.is_none() {
+            context.argument = Some(argument);
+        }
+        self
+    }
+
+    // Record the file the input was read from.
+    pub fn in_file(mut self, file: &str) -> Self {
+        let context = self.context_mut();
+        if context.file.is_none() {
+            context.file = Some(file.to_string());
+        }
+        self
+    }
+
+    // A span found while parsing a part of an input is relative to that part. Shift it
+    // by the offset of the part, so that it is relative to the whole input.
+    pub fn shift_span(mut self, offset: usize) -> Self {
+        if let Some(span) = self.context_mut().span.as_mut() {
+            span.start += offset;
+            span.end += offset;
+        }
+        self
+    }
+
+    // Suggest how to fix the input, unless something more specific already has.
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        let context = self.context_mut();
+        if context.help.is_none() {
+            context.help = Some(help.into());
+        }
+        self
+    }
+
+    // Record the byte range of the offending input, unless it is already known.
+    pub fn with_span(mut self, start: usize, end: usize) -> Self {
+        let context = self.context_mut();
+        if context.span.is_none() {
+            context.span = Some(InputSpan { start, end });
+        }
+        self
+    }
+}
+
+impl From<rust_decimal::Error> for SplittingError {
+    fn from(e: rust_decimal::Error) -> SplittingError {
+        SplittingError::DecimalParsingError(e.to_string().into())
+    }
+}
+
+// Required for main and Box<dyn std::error::Error>> returns to not complain
+impl Error for SplittingError {}
+
+impl fmt::Display for SplittingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
@@ -0,0 +1,321 @@
+use crate::core::receipt::{
+    ErrorContext, Receipt, ReceiptItem, SplittingError, LEFTOVER_ITEM_NAME, TOTAL_ITEM_NAME,
+};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{ToPrimitive, Zero};
+use rust_decimal::prelude::*;
+
+// Exact fraction path of the split engine, used when Receipt.arithmetic is Exact.
+// Every share is kept as a fraction until it is converted to cents, and each row is
+// rounded so that it adds up to its value exactly.
+
+fn decimal_to_ratio(value: Decimal) -> BigRational {
+    BigRational::new(
+        BigInt::from(value.mantissa()),
+        BigInt::from(10).pow(value.scale()),
+    )
+}
+
+fn cents_to_decimal(cents: &BigInt) -> Result<Decimal, SplittingError> {
+    cents
+        .to_i64()
+        .map(|cents| Decimal::new(cents, 2))
+        .ok_or_else(|| {
+            SplittingError::InternalError(
+                ErrorContext::new(format!("The amount of {} cents is too large.", cents))
+                    .with_value(cents),
+            )
+        })
+}
+
+// Split `weights` proportionally such that the result adds up to `value` exactly.
+fn split_by_weights(
+    value: &BigRational,
+    weights: &[BigRational],
+) -> Result<Vec<BigRational>, SplittingError> {
+    let denominator: BigRational = weights.iter().sum();
+    if denominator.is_zero() {
+        return Err(SplittingError::InvalidShareConfiguration(
+            ErrorContext::new(format!(
+                "The share ratios used to split {} add up to zero.",
+                value
+            ))
+            .with_value(value),
+        ));
+    }
+    Ok(weights
+        .iter()
+        .map(|weight| value * weight / &denominator)
+        .collect())
+}
+
+// Round exact shares to cents such that they add up to `value` rounded to cents. Every
+// share is either rounded down or up, and the extra cents go to the shares with the
+// largest remainders. The remainders include what each person has gained or lost in
+// earlier rows (`carried`), so that rounding errors do not pile up on the same people.
+fn apportion_cents(
+    value: Decimal,
+    shares: &[BigRational],
+    carried: &mut [BigRational],
+) -> Result<Vec<Decimal>, SplittingError> {
+    let hundred = BigRational::from_integer(BigInt::from(100));
+    let target = (decimal_to_ratio(value.round_dp(2)) * &hundred).to_integer();
+
+    let exact_cents: Vec<BigRational> = shares.iter().map(|share| share * &hundred).collect();
+    let mut cents: Vec<BigInt> = exact_cents.iter().map(|x| x.floor().to_integer()).collect();
+    let mut deficit: BigInt = &target - cents.iter().sum::<BigInt>();
+
+    // People who do not share the item never receive any of its cents.
+    let mut candidates: Vec<usize> = (0..shares.len())
+        .filter(|&idx| !shares[idx].is_zero())
+        .collect();
+    candidates.sort_by(|&a, &b| {
+        let remainder_a = &exact_cents[a] - exact_cents[a].floor() + &carried[a];
+        let remainder_b = &exact_cents[b] - exact_cents[b].floor() + &carried[b];
+        remainder_b.cmp(&remainder_a)
+    });
+
+    if !candidates.is_empty() {
+        for &idx in candidates.iter().cycle() {
+            if deficit <= BigInt::zero() {
+                break;
+            }
+            cents[idx] += 1;
+            deficit -= 1;
+        }
+    }
+
+    for (idx, exact) in exact_cents.iter().enumerate() {
+        carried[idx] += exact - BigRational::from_integer(cents[idx].clone());
+    }
+
+    cents.iter().map(cents_to_decimal).collect()
+}
+
+// The name, value and exact shares of a row of the splits.
+type ExactRow<'a> = (&'a str, Decimal, Vec<BigRational>);
+
+impl Receipt {
+    // Exact split of an item, in the order of self.shared_by. A proportional item is split
+    // by the exact splits of the items before it (`overall_prop`), rather than by its
+    // share ratios, which were divided out in Decimal.
+    fn exact_item_split(
+        &self,
+        item_idx: usize,
+        item: &ReceiptItem,
+        overall_prop: &[BigRational],
+    ) -> Result<Vec<BigRational>, SplittingError> {
+        let weights: Vec<BigRational> = self
+            .shared_by
+            .iter()
+            .zip(overall_prop)
+            .map(|(person, prop)| {
+                item.shared_by
+                    .iter()
+                    .position(|name| name == person)
+                    .map(|pos| {
+                        if item.is_prop_dist {
+                            prop.clone()
+                        } else {
+                            decimal_to_ratio(item.share_ratio[pos])
+                        }
+                    })
+                    .unwrap_or_else(BigRational::zero)
+            })
+            .collect();
+        split_by_weights(&decimal_to_ratio(item.value), &weights).map_err(|_| {
+            SplittingError::InvalidShareConfiguration(
+                ErrorContext::new(format!(
+                    "The share ratios of the item {} add up to zero.",
+                    item.name
+                ))
+                .with_item(item_idx, &item.name),
+            )
+        })
+    }
+
+    // The exact shares of every row, the leftover included, along with its name and value.
+    fn exact_rows(&self, leftover_amount: Decimal) -> Result<Vec<ExactRow<'_>>, SplittingError> {
+        let mut rows: Vec<ExactRow> = Vec::new();
+
+        // Non-proportional items form the basis of how the leftover is split.
+        let mut overall_prop: Vec<BigRational> = vec![BigRational::zero(); self.shared_by.len()];
+
+        for (item_idx, item) in self.items.iter().enumerate() {
+            let exact_split = self.exact_item_split(item_idx, item, &overall_prop)?;
+            if !item.is_prop_dist {
+                for (idx, share) in exact_split.iter().enumerate() {
+                    overall_prop[idx] += share;
+                }
+            }
+            rows.push((item.name.as_str(), item.value, exact_split));
+        }
+
+        if leftover_amount > Decimal::ZERO {
+            let exact_split = split_by_weights(&decimal_to_ratio(leftover_amount), &overall_prop)
+                .map_err(|_| {
+                SplittingError::InvalidShareConfiguration(
+                    "The leftover amount cannot be split, as there are no items to base it on."
+                        .into(),
+                )
+            })?;
+            rows.push((LEFTOVER_ITEM_NAME, leftover_amount, exact_split));
+        }
+        Ok(rows)
+    }
+
+    pub(crate) fn calculate_exact_splits(
+        &self,
+        leftover_amount: Decimal,
+    ) -> Result<(Vec<&str>, Vec<Vec<Decimal>>), SplittingError> {
+        let mut carried: Vec<BigRational> = vec![BigRational::zero(); self.shared_by.len()];
+        let mut all_splits: Vec<Vec<Decimal>> = Vec::new();
+        let mut item_names: Vec<&str> = Vec::new();
+
+        for (name, value, exact_split) in self.exact_rows(leftover_amount)? {
+            let mut splits = apportion_cents(value, &exact_split, &mut carried)?;
+            splits.push(value);
+            all_splits.push(splits);
+            item_names.push(name);
+        }
+
+        // Every row is in whole cents already, so the totals need no further rounding.
+        let total_split: Vec<Decimal> = (0..(self.shared_by.len() + 1))
+            .map(|i| all_splits.iter().map(|v| v[i]).sum::<Decimal>().round_dp(2))
+            .collect();
+        all_splits.push(total_split);
+        item_names.push(TOTAL_ITEM_NAME);
+
+        Ok((item_names, all_splits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::receipt::{Arithmetic, Receipt};
+    use crate::utils;
+    use num_bigint::BigInt;
+    use num_rational::BigRational;
+    use rust_decimal::prelude::*;
+
+    fn assert_rows_reconcile(splits: &[Vec<Decimal>]) {
+        for row in splits {
+            let (value, shares) = row.split_last().unwrap();
+            assert_eq!(
+                shares.iter().sum::<Decimal>(),
+                value.round_dp(2),
+                "{:?}",
+                row
+            );
+        }
+    }
+
+    #[test]
+    fn test_calculate_exact_splits() {
+        let mut receipt = Receipt::new(dec![300], vec!["Alice", "Bob", "Marshall"]).unwrap();
+        receipt.arithmetic = Arithmetic::Exact;
+        receipt
+            .add_item_split_by_ratio(
+                dec![200],
+                "Food".into(),
+                utils::strs_to_strings(vec!["Alice", "Bob", "Marshall"]),
+                None,
+            )
+            .unwrap();
+        receipt
+            .add_item_split_by_ratio(
+                dec![50],
+                "Drinks".into(),
+                utils::strs_to_strings(vec!["Alice", "Bob"]),
+                None,
+            )
+            .unwrap();
+        let (_, splits) = receipt.calculate_splits().unwrap();
+        assert_eq!(
+            splits,
+            vec![
+                vec![dec![66.67], dec![66.67], dec![66.66], dec![200]],
+                vec![dec![25], dec![25], dec![0], dec![50]],
+                vec![dec![18.33], dec![18.33], dec![13.34], dec![50]],
+                vec![dec![110], dec![110], dec![80], dec![300]],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exact_splits_reconcile_for_adversarial_ratios() {
+        let people = vec!["A", "B", "C", "D", "E", "F", "G"];
+        let mut exact = Receipt::new(dec![2000], people.clone()).unwrap();
+        exact.arithmetic = Arithmetic::Exact;
+
+        // Prime weights, so that hardly any share divides into whole cents.
+        let ratios = [
+            dec![1],
+            dec![3],
+            dec![7],
+            dec![11],
+            dec![13],
+            dec![17],
+            dec![19],
+        ];
+        for idx in 0..40 {
+            let shared_by: Vec<String> = people
+                .iter()
+                .cycle()
+                .skip(idx)
+                .take(3 + idx % 5)
+                .map(|x| x.to_string())
+                .collect();
+            let share_ratio: Vec<Decimal> = ratios
+                .iter()
+                .cycle()
+                .skip(idx * 2)
+                .take(shared_by.len())
+                .cloned()
+                .collect();
+            exact
+                .add_item_split_by_ratio(
+                    dec![10.01] + Decimal::from(idx),
+                    format!("Item {}", idx),
+                    shared_by,
+                    Some(share_ratio),
+                )
+                .unwrap();
+        }
+        exact
+            .add_item_split_by_proportion(dec![33.33], "Tip".into(), utils::strs_to_strings(people))
+            .unwrap();
+
+        let (_, splits) = exact.calculate_splits().unwrap();
+        assert_rows_reconcile(&splits);
+
+        let totals = splits.last().unwrap();
+        assert_eq!(totals.last().unwrap(), &dec![2000]);
+        assert_eq!(
+            totals[..totals.len() - 1].iter().sum::<Decimal>(),
+            dec![2000]
+        );
+
+        // Rounding only moves cents between people: everyone's total is their exact share,
+        // proportional items and all, rounded down or up to the cent.
+        let (_, leftover) = exact.get_itemized_total_and_leftover();
+        let rows = exact.exact_rows(leftover).unwrap();
+        let hundred = BigRational::from_integer(BigInt::from(100));
+        for (idx, total) in totals[..totals.len() - 1].iter().enumerate() {
+            let exact_cents: BigRational = rows
+                .iter()
+                .map(|(_, _, shares)| &shares[idx])
+                .sum::<BigRational>()
+                * &hundred;
+            let cents = BigInt::from(total.mantissa());
+            assert!(
+                cents == exact_cents.floor().to_integer()
+                    || cents == exact_cents.ceil().to_integer(),
+                "{} is not {} rounded to the cent",
+                total,
+                exact_cents / &hundred
+            );
+        }
+    }
+}
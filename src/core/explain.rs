@@ -0,0 +1,259 @@
+use crate::core::locale::Locale;
+use crate::core::receipt::{
+    ErrorContext, Person, Receipt, SplittingError, LEFTOVER_ITEM_NAME, TOTAL_ITEM_NAME,
+};
+use rust_decimal::prelude::*;
+
+// How a person's part of an item was worked out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShareBasis {
+    // Their weight out of the weights of everyone sharing the item.
+    Ratio { weight: Decimal, total: Decimal },
+    // In proportion to what they shared of the items before it, as with taxes and tips.
+    Proportional,
+    // In proportion to what they shared of all the items that are not proportional.
+    Leftover,
+}
+
+// A person's part of one row of the splits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExplainedShare {
+    pub item: String,
+    pub value: Decimal,
+    pub basis: ShareBasis,
+    pub fraction: Decimal,
+    // Their part before it was rounded to cents, and after.
+    pub exact: Decimal,
+    pub amount: Decimal,
+}
+
+// Everything that adds up to a person's total, so that it can be checked by hand.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Explanation {
+    pub person: Person,
+    pub shares: Vec<ExplainedShare>,
+    // Cents gained or lost by rounding each share, so that the total is whole cents.
+    pub rounding: Decimal,
+    pub total: Decimal,
+}
+
+fn format_weight(weight: Decimal, locale: &Locale) -> String {
+    weight
+        .normalize()
+        .to_string()
+        .replace('.', &locale.decimal.to_string())
+}
+
+impl ExplainedShare {
+    pub fn describe(&self, locale: &Locale) -> String {
+        let percentage = format!(
+            "{}%",
+            locale.format_amount(self.fraction * Decimal::ONE_HUNDRED)
+        );
+        let basis = match &self.basis {
+            ShareBasis::Ratio { weight, total } if weight == total => "all".to_string(),
+            ShareBasis::Ratio { weight, total } => format!(
+                "{} of {} parts ({})",
+                format_weight(*weight, locale),
+                format_weight(*total, locale),
+                percentage
+            ),
+            ShareBasis::Proportional => {
+                format!("{}, in proportion to the items before it,", percentage)
+            }
+            ShareBasis::Leftover => format!("{}, in proportion to the items,", percentage),
+        };
+        format!(
+            "{}: {} of {} = {}",
+            self.item,
+            basis,
+            locale.format_money(self.value),
+            locale.format_money(self.amount)
+        )
+    }
+}
+
+impl Explanation {
+    // One line per share, followed by the rounding, if any, and the total.
+    pub fn describe(&self, locale: &Locale) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .shares
+            .iter()
+            .map(|share| share.describe(locale))
+            .collect();
+        if !self.rounding.is_zero() {
+            let sign = if self.rounding > Decimal::ZERO {
+                "+"
+            } else {
+                ""
+            };
+            lines.push(format!(
+                "Rounding: {}{}",
+                sign,
+                locale.format_money(self.rounding)
+            ));
+        }
+        lines.push(format!(
+            "{}: {}",
+            TOTAL_ITEM_NAME,
+            locale.format_money(self.total)
+        ));
+        lines
+    }
+}
+
+impl Receipt {
+    // How the person's total was reached: the items they share, their part of each
+    // before and after rounding, and the cents that rounding moved.
+    pub fn explain(&self, person: &str) -> Result<Explanation, SplittingError> {
+        let person_idx = self
+            .shared_by
+            .iter()
+            .position(|name| name == person)
+            .ok_or_else(|| {
+                SplittingError::InvalidArgument(
+                    ErrorContext::new(format!("{} does not share this receipt.", person))
+                        .with_person(person)
+                        .with_help(format!(
+                            "explain the share of one of {}",
+                            self.shared_by.join(", ")
+                        )),
+                )
+            })?;
+        let (item_names, splits) = self.calculate_splits()?;
+
+        let mut shares: Vec<ExplainedShare> = Vec::new();
+        for (item, rounded) in self.items.iter().zip(splits.iter()) {
+            let Some(pos) = item.shared_by.iter().position(|name| name == person) else {
+                continue;
+            };
+            let weight = item.share_ratio[pos];
+            let total: Decimal = item.share_ratio.iter().sum();
+            let fraction = weight.checked_div(total).unwrap_or_default();
+            let basis = if item.is_prop_dist {
+                ShareBasis::Proportional
+            } else {
+                ShareBasis::Ratio { weight, total }
+            };
+            shares.push(ExplainedShare {
+                item: item.name.clone(),
+                value: item.value,
+                basis,
+                fraction,
+                exact: item.value * fraction,
+                amount: rounded[person_idx],
+            });
+        }
+
+        if item_names.contains(&LEFTOVER_ITEM_NAME) {
+            let (_, leftover_amount) = self.get_itemized_total_and_leftover();
+            let overall_prop = self.calculate_overall_proportion(true);
+            let fraction = overall_prop[person_idx]
+                .checked_div(overall_prop.iter().sum())
+                .unwrap_or_default();
+            if !fraction.is_zero() {
+                shares.push(ExplainedShare {
+                    item: LEFTOVER_ITEM_NAME.into(),
+                    value: leftover_amount,
+                    basis: ShareBasis::Leftover,
+                    fraction,
+                    exact: leftover_amount * fraction,
+                    amount: splits[self.items.len()][person_idx],
+                });
+            }
+        }
+
+        // The last row is always the total owed by each person.
+        let total = splits.last().map(|row| row[person_idx]).ok_or_else(|| {
+            SplittingError::InternalError("The calculated splits have no total row.".into())
+        })?;
+        let exact_total: Decimal = shares.iter().map(|share| share.exact).sum();
+
+        Ok(Explanation {
+            person: self.shared_by[person_idx].clone(),
+            shares,
+            rounding: total - exact_total.round_dp(2),
+            total,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::explain::ShareBasis;
+    use crate::core::locale::Locale;
+    use crate::core::receipt::{Receipt, SplittingError};
+    use crate::utils;
+    use rust_decimal::prelude::*;
+
+    #[test]
+    fn explain_a_persons_share() {
+        let mut receipt = Receipt::new(dec![100], vec!["Alice", "Bob", "Marshall"]).unwrap();
+        receipt
+            .add_item_split_by_ratio(
+                dec![50],
+                "Food".into(),
+                utils::strs_to_strings(vec!["Alice", "Bob", "Marshall"]),
+                None,
+            )
+            .unwrap();
+        receipt
+            .add_item_split_by_ratio(
+                dec![20],
+                "Wine".into(),
+                utils::strs_to_strings(vec!["Alice", "Bob"]),
+                Some(vec![dec![2], dec![1]]),
+            )
+            .unwrap();
+        receipt
+            .add_item_split_by_proportion(
+                dec![10],
+                "Tip".into(),
+                utils::strs_to_strings(vec!["Alice", "Bob", "Marshall"]),
+            )
+            .unwrap();
+
+        let explanation = receipt.explain("Alice").unwrap();
+        let amounts: Vec<(&str, Decimal)> = explanation
+            .shares
+            .iter()
+            .map(|share| (share.item.as_str(), share.amount))
+            .collect();
+        assert_eq!(
+            amounts,
+            vec![
+                ("Food", dec![16.67]),
+                ("Wine", dec![13.33]),
+                ("Tip", dec![4.29]),
+                ("<leftover>", dec![8.57]),
+            ]
+        );
+        assert_eq!(
+            explanation.shares[1].basis,
+            ShareBasis::Ratio {
+                weight: dec![2],
+                total: dec![3]
+            }
+        );
+        assert_eq!(explanation.total, dec![42.86]);
+        assert_eq!(explanation.rounding, Decimal::ZERO);
+
+        // Each of Bob's shares rounds up, by a cent between them.
+        let explanation = receipt.explain("Bob").unwrap();
+        assert_eq!(explanation.total, dec![33.34]);
+        assert_eq!(explanation.rounding, dec![0.01]);
+
+        // Marshall has no wine, so it is not part of his explanation.
+        let explanation = receipt.explain("Marshall").unwrap();
+        assert!(explanation.shares.iter().all(|share| share.item != "Wine"));
+        assert_eq!(
+            explanation.describe(&Locale::default())[0],
+            "Food: 1 of 3 parts (33.33%) of 50.00 = 16.67"
+        );
+
+        assert!(matches!(
+            receipt.explain("Dave"),
+            Err(SplittingError::InvalidArgument(_))
+        ));
+    }
+}
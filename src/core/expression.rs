@@ -0,0 +1,310 @@
+use crate::core::locale::{Locale, LOCALES};
+use crate::core::receipt::{ErrorContext, SplittingError};
+use rust_decimal::prelude::*;
+
+// Amounts may be written as arithmetic, as receipts often list a quantity and a unit
+// price, or a bill to be halved:
+//
+//     expression = term { ("+" | "-") term }
+//     term       = factor { ("*" | "/") factor }
+//     factor     = ("-" | "+") factor | primary { "%" }
+//     primary    = number | "(" expression ")"
+//
+// A percentage is a hundredth, so that '120*10%' is 12. Everything is evaluated with
+// Decimal, so '0.1+0.2' is exactly 0.3, and overflow or division by zero is an error
+// rather than a wrong amount. Numbers are written with the locale's separators, so
+// '1.234,50' in German, and a grouping separator must split the digits into threes so
+// that '12,50' is not quietly read as 1250.
+
+// Brackets are evaluated recursively, so how deeply they nest is limited to keep a long
+// run of '(' from overflowing the stack.
+const MAX_NESTING: usize = 64;
+
+struct Evaluator<'a> {
+    input: &'a str,
+    locale: &'a Locale,
+    position: usize,
+    // How many brackets enclose the position.
+    depth: usize,
+}
+
+impl Evaluator<'_> {
+    // The next character that is not whitespace, without consuming it.
+    fn peek(&mut self) -> Option<char> {
+        let rest = &self.input[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+        rest.trim_start().chars().next()
+    }
+
+    fn error(&self, reason: &str, start: usize, end: usize) -> SplittingError {
+        SplittingError::DecimalParsingError(
+            ErrorContext::new(format!("{} is not a valid amount: {}", self.input, reason))
+                .with_value(self.input)
+                .with_span(start, end)
+                .with_help("amounts are numbers, or arithmetic on them, such as 12.50 or 3*4.50+2"),
+        )
+    }
+
+    // A grouping separator in the wrong place is most likely the decimal separator of
+    // another locale, so suggest that locale.
+    fn grouping_error(&self, separator: char, start: usize, end: usize) -> SplittingError {
+        let suggestion = LOCALES.iter().find(|name| {
+            name.parse::<Locale>()
+                .is_ok_and(|locale| locale.decimal == separator)
+        });
+        let help = match suggestion {
+            Some(name) => format!(
+                "if '{}' separates the decimals, switch to a locale that uses it, such as {}",
+                separator, name
+            ),
+            None => format!(
+                "write the digits in groups of three, as in 1{}234",
+                separator
+            ),
+        };
+        SplittingError::DecimalParsingError(
+            ErrorContext::new(format!(
+                "{} is not a valid amount: '{}' must separate groups of three digits",
+                self.input, separator
+            ))
+            .with_value(self.input)
+            .with_span(start, end)
+            .with_help(help),
+        )
+    }
+
+    fn overflow(&self, start: usize) -> SplittingError {
+        self.error("it is too large", start, self.position)
+    }
+
+    fn expression(&mut self) -> Result<Decimal, SplittingError> {
+        let start = self.position;
+        let mut value = self.term()?;
+        loop {
+            let operator = match self.peek() {
+                Some(operator @ ('+' | '-')) => operator,
+                _ => return Ok(value),
+            };
+            self.position += 1;
+            let rhs = self.term()?;
+            value = match operator {
+                '+' => value.checked_add(rhs),
+                _ => value.checked_sub(rhs),
+            }
+            .ok_or_else(|| self.overflow(start))?;
+        }
+    }
+
+    fn term(&mut self) -> Result<Decimal, SplittingError> {
+        let start = self.position;
+        let mut value = self.factor()?;
+        loop {
+            let operator = match self.peek() {
+                Some(operator @ ('*' | '/')) => operator,
+                _ => return Ok(value),
+            };
+            self.position += 1;
+            let rhs_start = self.position;
+            let rhs = self.factor()?;
+            value = match operator {
+                '*' => value.checked_mul(rhs).ok_or_else(|| self.overflow(start))?,
+                _ if rhs.is_zero() => {
+                    return Err(self.error("it divides by zero", rhs_start, self.position))
+                }
+                _ => value.checked_div(rhs).ok_or_else(|| self.overflow(start))?,
+            };
+        }
+    }
+
+    fn factor(&mut self) -> Result<Decimal, SplittingError> {
+        // Signs are counted rather than recursed into, as there may be any number of them.
+        let mut negative = false;
+        while let Some(sign @ ('-' | '+')) = self.peek() {
+            self.position += 1;
+            negative ^= sign == '-';
+        }
+        let mut value = self.primary()?;
+        while self.peek() == Some('%') {
+            self.position += 1;
+            value /= Decimal::ONE_HUNDRED;
+        }
+        Ok(if negative { -value } else { value })
+    }
+
+    fn primary(&mut self) -> Result<Decimal, SplittingError> {
+        let start = self.position;
+        match self.peek() {
+            Some('(') => {
+                let open = self.position;
+                if self.depth == MAX_NESTING {
+                    return Err(self.error(
+                        &format!("brackets are nested more than {} deep", MAX_NESTING),
+                        open,
+                        open + 1,
+                    ));
+                }
+                self.position += 1;
+                self.depth += 1;
+                let value = self.expression()?;
+                self.depth -= 1;
+                if self.peek() != Some(')') {
+                    return Err(self.error("the bracket is never closed", open, open + 1));
+                }
+                self.position += 1;
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() || c == self.locale.decimal => {
+                // A grouping separator is only part of the number when a digit follows. It
+                // must come after one to three digits, or a previous group, and before
+                // exactly three, and never in the decimals.
+                let number_start = self.position;
+                let mut number = String::new();
+                let mut group_digits = 0;
+                let mut in_decimals = false;
+                let mut chars = self.input[number_start..].char_indices().peekable();
+                while let Some((idx, c)) = chars.next() {
+                    let next_is_digit = chars.peek().is_some_and(|(_, next)| next.is_ascii_digit());
+                    if c.is_ascii_digit() {
+                        number.push(c);
+                        group_digits += 1;
+                    } else if c == self.locale.decimal {
+                        number.push('.');
+                        in_decimals = true;
+                    } else if Some(c) == self.locale.grouping && next_is_digit {
+                        let group_start = number_start + idx + c.len_utf8();
+                        let group = self.input[group_start..]
+                            .chars()
+                            .take_while(|c| c.is_ascii_digit())
+                            .count();
+                        if in_decimals || !(1..=3).contains(&group_digits) || group != 3 {
+                            return Err(self.grouping_error(c, number_start, group_start + group));
+                        }
+                        group_digits = 0;
+                        continue;
+                    } else {
+                        break;
+                    }
+                    self.position = number_start
+                        + chars
+                            .peek()
+                            .map_or(self.input.len() - number_start, |&(idx, _)| idx);
+                }
+                number.parse().map_err(|e: rust_decimal::Error| {
+                    self.error(&e.to_string(), number_start, self.position)
+                })
+            }
+            Some(c) => Err(self.error(
+                &format!("expected a number, but found '{}'", c),
+                self.position,
+                self.position + c.len_utf8(),
+            )),
+            None if start == 0 => Err(self.error("it is empty", 0, 0)),
+            None => Err(self.error("expected a number at the end", self.position, self.position)),
+        }
+    }
+}
+
+// Evaluate an amount, which may be arithmetic. Error spans are relative to the input.
+pub fn evaluate(input: &str) -> Result<Decimal, SplittingError> {
+    evaluate_in(input, &Locale::default())
+}
+
+// Evaluate an amount written with the separators of a locale.
+pub fn evaluate_in(input: &str, locale: &Locale) -> Result<Decimal, SplittingError> {
+    let mut evaluator = Evaluator {
+        input,
+        locale,
+        position: 0,
+        depth: 0,
+    };
+    let value = evaluator.expression()?;
+    match evaluator.peek() {
+        None => Ok(value),
+        Some(c) => Err(evaluator.error(
+            &format!("unexpected '{}'", c),
+            evaluator.position,
+            evaluator.position + c.len_utf8(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::expression::{evaluate, evaluate_in};
+    use crate::core::locale::Locale;
+    use crate::core::receipt::{InputSpan, SplittingError};
+    use rust_decimal::prelude::*;
+
+    #[test]
+    fn evaluate_arithmetic() {
+        assert_eq!(evaluate("12.50").unwrap(), dec![12.50]);
+        assert_eq!(evaluate("3*4.50+2").unwrap(), dec![15.5]);
+        assert_eq!(evaluate("120 / 2").unwrap(), dec![60]);
+        assert_eq!(evaluate("2*(3+4)").unwrap(), dec![14]);
+        assert_eq!(evaluate("10-2-3").unwrap(), dec![5]);
+        assert_eq!(evaluate("-20").unwrap(), dec![-20]);
+        assert_eq!(evaluate("-(5+5)*2").unwrap(), dec![-20]);
+        assert_eq!(evaluate("120*10%").unwrap(), dec![12]);
+        assert_eq!(evaluate("0.1+0.2").unwrap(), dec![0.3]);
+        assert_eq!(evaluate("100/3*3").unwrap().round_dp(10), dec![100]);
+    }
+
+    #[test]
+    fn evaluate_with_separators_of_a_locale() {
+        assert_eq!(evaluate("1,234.50").unwrap(), dec![1234.50]);
+        let german: Locale = "de".parse().unwrap();
+        assert_eq!(evaluate_in("12,50", &german).unwrap(), dec![12.50]);
+        assert_eq!(evaluate_in("1.234,5*2", &german).unwrap(), dec![2469]);
+        let french: Locale = "fr".parse().unwrap();
+        assert_eq!(evaluate_in("1 234,50 + 1", &french).unwrap(), dec![1235.50]);
+
+        // Digits must be grouped in threes, or the separator was meant as a decimal one.
+        let span = |input: &str| {
+            let error = evaluate(input).unwrap_err();
+            assert!(matches!(error, SplittingError::DecimalParsingError(_)));
+            assert!(error
+                .context()
+                .help
+                .as_ref()
+                .unwrap()
+                .contains("such as de"));
+            error.context().span.map(|span| (span.start, span.end))
+        };
+        assert_eq!(span("12,50"), Some((0, 5)));
+        assert_eq!(span("1,2345"), Some((0, 6)));
+        assert_eq!(span("1234,567"), Some((0, 8)));
+        assert_eq!(span("1.5,000"), Some((0, 7)));
+        assert_eq!(evaluate("1,234,567").unwrap(), dec![1234567]);
+
+        // A separator without a digit after it ends the number.
+        let error = evaluate_in("12.", &german).unwrap_err();
+        assert_eq!(error.context().span, Some(InputSpan { start: 2, end: 3 }));
+    }
+
+    #[test]
+    fn invalid_expressions_point_at_the_problem() {
+        let span = |input: &str| {
+            let error = evaluate(input).unwrap_err();
+            assert!(matches!(error, SplittingError::DecimalParsingError(_)));
+            error.context().span.map(|span| (span.start, span.end))
+        };
+        assert_eq!(span("x"), Some((0, 1)));
+        assert_eq!(span(""), Some((0, 0)));
+        assert_eq!(span("3*"), Some((2, 2)));
+        assert_eq!(span("3 + 4)"), Some((5, 6)));
+        assert_eq!(span("(3+4"), Some((0, 1)));
+        assert_eq!(span("10/(2-2)"), Some((3, 8)));
+        assert_eq!(span("1.2.3"), Some((0, 5)));
+
+        assert_eq!(span(&"(".repeat(60000)), Some((64, 65)));
+        let nested = format!("{}1{}", "(".repeat(64), ")".repeat(64));
+        assert_eq!(evaluate(&nested).unwrap(), dec![1]);
+        assert_eq!(
+            evaluate(&format!("{}5", "-".repeat(60001))).unwrap(),
+            dec![-5]
+        );
+
+        let error = evaluate("79228162514264337593543950335*2").unwrap_err();
+        assert_eq!(error.context().span, Some(InputSpan { start: 0, end: 31 }));
+    }
+}
@@ -0,0 +1,354 @@
+use crate::core::receipt::{
+    ErrorContext, PaymentMethod, Person, Receipt, ReceiptItem, SplittingError,
+};
+use rust_decimal::prelude::*;
+
+// A single change to a receipt. Edits are replayed on top of the receipt they were
+// first applied to, so they only need to describe the change, not how to reverse it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReceiptEdit {
+    AddItem {
+        value: Decimal,
+        name: String,
+        shared_by: Vec<Person>,
+        share_ratio: Option<Vec<Decimal>>,
+    },
+    AddProportionalItem {
+        value: Decimal,
+        name: String,
+        shared_by: Vec<Person>,
+    },
+    EditItemName {
+        item_idx: usize,
+        name: String,
+    },
+    EditItemValue {
+        item_idx: usize,
+        value: Decimal,
+    },
+    RemoveItem {
+        item_idx: usize,
+    },
+    ToggleSharer {
+        item_idx: usize,
+        person: Person,
+    },
+    ChangeTotal {
+        value: Decimal,
+    },
+    AddPayment {
+        payer: Person,
+        amount: Decimal,
+        method: PaymentMethod,
+    },
+}
+
+impl ReceiptEdit {
+    // Whether applying `self` right after `other` makes `other` redundant, like two
+    // keystrokes in the same item's name.
+    fn supersedes(&self, other: &ReceiptEdit) -> bool {
+        match (self, other) {
+            (Self::EditItemName { item_idx: a, .. }, Self::EditItemName { item_idx: b, .. }) => {
+                a == b
+            }
+            (Self::EditItemValue { item_idx: a, .. }, Self::EditItemValue { item_idx: b, .. }) => {
+                a == b
+            }
+            (Self::ChangeTotal { .. }, Self::ChangeTotal { .. }) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Receipt {
+    fn item_mut(&mut self, item_idx: usize) -> Result<&mut ReceiptItem, SplittingError> {
+        let item_count = self.items.len();
+        self.items.get_mut(item_idx).ok_or_else(|| {
+            SplittingError::InvalidArgument(
+                ErrorContext::new(format!(
+                    "There is no item {} in a receipt with {} items.",
+                    item_idx + 1,
+                    item_count
+                ))
+                .with_value(item_idx + 1),
+            )
+        })
+    }
+
+    pub fn remove_item(&mut self, item_idx: usize) -> Result<ReceiptItem, SplittingError> {
+        self.item_mut(item_idx)?;
+        Ok(self.items.remove(item_idx))
+    }
+
+    // Add the person to the people sharing the item, or remove them if they already
+    // are. The last person sharing an item cannot be removed.
+    pub fn toggle_sharer(&mut self, item_idx: usize, person: &str) -> Result<(), SplittingError> {
+        if !self.shared_by.iter().any(|name| name == person) {
+            return Err(SplittingError::InvalidFieldError(
+                ErrorContext::new(format!(
+                    "{} is not one of the people sharing the receipt.",
+                    person
+                ))
+                .with_person(person),
+            ));
+        }
+
+        let item = self.item_mut(item_idx)?;
+        match item.shared_by.iter().position(|name| name == person) {
+            Some(_) if item.shared_by.len() == 1 => Err(SplittingError::NotEnoughPeopleError(
+                ErrorContext::new(format!(
+                    "{} is the only person sharing {}. It must be shared by at least 1 person.",
+                    person, item.name
+                ))
+                .with_item(item_idx, &item.name)
+                .with_person(person),
+            )),
+            Some(pos) => {
+                item.shared_by.remove(pos);
+                item.share_ratio.remove(pos);
+                Ok(())
+            }
+            None => {
+                item.shared_by.push(person.to_string());
+                item.share_ratio.push(Decimal::ONE);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn apply_edit(&mut self, edit: &ReceiptEdit) -> Result<(), SplittingError> {
+        match edit {
+            ReceiptEdit::AddItem {
+                value,
+                name,
+                shared_by,
+                share_ratio,
+            } => {
+                self.add_item_split_by_ratio(
+                    *value,
+                    name.clone(),
+                    shared_by.clone(),
+                    share_ratio.clone(),
+                )?;
+            }
+            ReceiptEdit::AddProportionalItem {
+                value,
+                name,
+                shared_by,
+            } => {
+                self.add_item_split_by_proportion(*value, name.clone(), shared_by.clone())?;
+            }
+            ReceiptEdit::EditItemName { item_idx, name } => {
+                self.item_mut(*item_idx)?.name = name.clone();
+            }
+            ReceiptEdit::EditItemValue { item_idx, value } => {
+                self.item_mut(*item_idx)?.value = *value;
+            }
+            ReceiptEdit::RemoveItem { item_idx } => {
+                self.remove_item(*item_idx)?;
+            }
+            ReceiptEdit::ToggleSharer { item_idx, person } => {
+                self.toggle_sharer(*item_idx, person)?;
+            }
+            ReceiptEdit::ChangeTotal { value } => {
+                self.value = *value;
+            }
+            ReceiptEdit::AddPayment {
+                payer,
+                amount,
+                method,
+            } => {
+                self.add_payment(payer.clone(), *amount, method.clone())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Log of the edits made to a receipt, supporting undo and redo. Undoing replays the
+// remaining edits on the original receipt, so no edit needs to know its inverse.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EditHistory {
+    initial: Receipt,
+    current: Receipt,
+    done: Vec<ReceiptEdit>,
+    undone: Vec<ReceiptEdit>,
+}
+
+impl EditHistory {
+    pub fn new(receipt: Receipt) -> Self {
+        EditHistory {
+            initial: receipt.clone(),
+            current: receipt,
+            done: vec![],
+            undone: vec![],
+        }
+    }
+
+    pub fn receipt(&self) -> &Receipt {
+        &self.current
+    }
+
+    pub fn edits(&self) -> &[ReceiptEdit] {
+        &self.done
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    // Apply the edit and record it. A failed edit leaves the receipt and the history
+    // untouched. Applying a new edit discards anything that could have been redone.
+    pub fn apply(&mut self, edit: ReceiptEdit) -> Result<(), SplittingError> {
+        self.current.apply_edit(&edit)?;
+        self.done.push(edit);
+        self.undone.clear();
+        Ok(())
+    }
+
+    // Like apply, but merges the edit into the previous one if it supersedes it, so
+    // that typing a name or value character by character is undone in one step.
+    pub fn amend(&mut self, edit: ReceiptEdit) -> Result<(), SplittingError> {
+        match self.done.last() {
+            Some(last) if edit.supersedes(last) => {
+                self.current.apply_edit(&edit)?;
+                *self.done.last_mut().unwrap() = edit;
+                self.undone.clear();
+                Ok(())
+            }
+            _ => self.apply(edit),
+        }
+    }
+
+    // Returns whether there was anything to undo.
+    pub fn undo(&mut self) -> Result<bool, SplittingError> {
+        match self.done.pop() {
+            Some(edit) => {
+                self.current = Self::replay(self.initial.clone(), &self.done)?;
+                self.undone.push(edit);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    // Returns whether there was anything to redo.
+    pub fn redo(&mut self) -> Result<bool, SplittingError> {
+        match self.undone.pop() {
+            Some(edit) => {
+                self.current.apply_edit(&edit)?;
+                self.done.push(edit);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    pub fn replay(mut receipt: Receipt, edits: &[ReceiptEdit]) -> Result<Receipt, SplittingError> {
+        for edit in edits {
+            receipt.apply_edit(edit)?;
+        }
+        Ok(receipt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::history::{EditHistory, ReceiptEdit};
+    use crate::core::receipt::Receipt;
+    use crate::utils;
+    use rust_decimal::prelude::*;
+
+    fn add_item(value: Decimal, name: &str, shared_by: Vec<&str>) -> ReceiptEdit {
+        ReceiptEdit::AddItem {
+            value,
+            name: name.into(),
+            shared_by: utils::strs_to_strings(shared_by),
+            share_ratio: None,
+        }
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let receipt = Receipt::new(dec![300], vec!["Alice", "Bob"]).unwrap();
+        let mut history = EditHistory::new(receipt.clone());
+        history
+            .apply(add_item(dec![200], "Food", vec!["Alice", "Bob"]))
+            .unwrap();
+        history
+            .apply(ReceiptEdit::ToggleSharer {
+                item_idx: 0,
+                person: "Bob".into(),
+            })
+            .unwrap();
+        assert_eq!(history.receipt().items[0].shared_by, vec!["Alice"]);
+
+        assert!(history.undo().unwrap());
+        assert_eq!(history.receipt().items[0].shared_by, vec!["Alice", "Bob"]);
+        assert!(history.undo().unwrap());
+        assert_eq!(history.receipt(), &receipt);
+        assert!(!history.undo().unwrap());
+
+        assert!(history.redo().unwrap());
+        assert!(history.redo().unwrap());
+        assert_eq!(history.receipt().items[0].shared_by, vec!["Alice"]);
+        assert!(!history.redo().unwrap());
+
+        // A new edit discards what could have been redone.
+        history.undo().unwrap();
+        history
+            .apply(ReceiptEdit::ChangeTotal { value: dec![250] })
+            .unwrap();
+        assert!(!history.can_redo());
+        assert_eq!(
+            EditHistory::replay(receipt, history.edits()).unwrap(),
+            *history.receipt()
+        );
+    }
+
+    #[test]
+    fn test_amend_merges_keystrokes() {
+        let receipt = Receipt::new(dec![300], vec!["Alice", "Bob"]).unwrap();
+        let mut history = EditHistory::new(receipt);
+        history
+            .apply(add_item(dec![200], "Item 1", vec!["Alice", "Bob"]))
+            .unwrap();
+        for name in ["W", "Wi", "Win", "Wine"] {
+            history
+                .amend(ReceiptEdit::EditItemName {
+                    item_idx: 0,
+                    name: name.into(),
+                })
+                .unwrap();
+        }
+        assert_eq!(history.edits().len(), 2);
+        assert_eq!(history.receipt().items[0].name, "Wine");
+
+        history.undo().unwrap();
+        assert_eq!(history.receipt().items[0].name, "Item 1");
+    }
+
+    #[test]
+    fn test_failed_edit_is_not_recorded() {
+        let receipt = Receipt::new(dec![300], vec!["Alice", "Bob"]).unwrap();
+        let mut history = EditHistory::new(receipt);
+        history
+            .apply(add_item(dec![200], "Food", vec!["Alice"]))
+            .unwrap();
+        assert!(history
+            .apply(ReceiptEdit::ToggleSharer {
+                item_idx: 0,
+                person: "Alice".into(),
+            })
+            .is_err());
+        assert!(history
+            .apply(ReceiptEdit::RemoveItem { item_idx: 3 })
+            .is_err());
+        assert_eq!(history.edits().len(), 1);
+        assert_eq!(history.receipt().items[0].shared_by, vec!["Alice"]);
+    }
+}
@@ -0,0 +1,192 @@
+use crate::core::receipt::{ErrorContext, SplittingError};
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// How amounts are written: the separators of decimals and of thousands, the currency
+// they are in, and the separator of the parts of a pattern, which cannot also be the
+// decimal separator. Amounts are read, and splits are shown, in the receipt's locale.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Locale {
+    pub decimal: char,
+    pub grouping: Option<char>,
+    pub delimiter: char,
+    pub currency: Option<String>,
+    // Whether the currency follows the amount, as in 12,50 €, rather than leading it.
+    pub currency_after: bool,
+}
+
+// The locales that can be chosen by name.
+pub const LOCALES: [&str; 4] = ["en", "de", "fr", "ch"];
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale {
+            decimal: '.',
+            grouping: Some(','),
+            delimiter: ',',
+            currency: None,
+            currency_after: false,
+        }
+    }
+}
+
+impl FromStr for Locale {
+    type Err = SplittingError;
+
+    // A language, optionally with a region, such as de or de-CH. Languages that write
+    // amounts alike share a locale.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_lowercase().replace('_', "-");
+        let (language, region) = match lower.split_once('-') {
+            Some((language, region)) => (language, Some(region)),
+            None => (lower.as_str(), None),
+        };
+        let english = Locale::default();
+        let continental = Locale {
+            decimal: ',',
+            grouping: Some('.'),
+            delimiter: ';',
+            currency_after: true,
+            ..english.clone()
+        };
+        match (language, region) {
+            ("ch", None) | (_, Some("ch")) => Ok(Locale {
+                grouping: Some('\''),
+                ..english
+            }),
+            ("en" | "ja" | "zh" | "ko", _) => Ok(english),
+            ("fr", _) => Ok(Locale {
+                grouping: Some(' '),
+                ..continental
+            }),
+            ("de" | "es" | "it" | "nl" | "pt" | "da", _) => Ok(continental),
+            _ => Err(SplittingError::InvalidArgument(
+                ErrorContext::new(format!(
+                    "{} is not a locale. Use one of {}, or a language such as es.",
+                    s,
+                    LOCALES.join(", ")
+                ))
+                .with_value(s)
+                .with_span(0, s.len()),
+            )),
+        }
+    }
+}
+
+impl Locale {
+    // Separate the parts of patterns with another character, such as ';' where the
+    // comma is the decimal separator. Patterns are split before amounts are read, so a
+    // grouping separator can be the delimiter, but then cannot be used in amounts.
+    pub fn set_delimiter(&mut self, delimiter: &str) -> Result<(), SplittingError> {
+        let mut chars = delimiter.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None)
+                if c != self.decimal
+                    && c != '.'
+                    && !c.is_alphanumeric()
+                    && !c.is_whitespace()
+                    && !"\"\\:=%+-*/()".contains(c) =>
+            {
+                self.delimiter = c;
+                Ok(())
+            }
+            _ => Err(SplittingError::InvalidArgument(
+                ErrorContext::new(format!(
+                    "{} cannot separate the parts of a pattern. Use a single character that \
+                    is not part of amounts, names or shares, such as ';' or '|'.",
+                    delimiter
+                ))
+                .with_value(delimiter)
+                .with_span(0, delimiter.len()),
+            )),
+        }
+    }
+
+    // An amount to the cent, with the locale's separators, as in 1.234,50.
+    pub fn format_amount(&self, value: Decimal) -> String {
+        let mut value = value.round_dp(2);
+        value.rescale(2);
+        let text = value.abs().to_string();
+        let (whole, cents) = text.split_once('.').unwrap_or((&text, "00"));
+
+        let mut grouped = String::new();
+        for (idx, digit) in whole.chars().enumerate() {
+            if idx > 0 && (whole.len() - idx) % 3 == 0 {
+                if let Some(grouping) = self.grouping {
+                    grouped.push(grouping);
+                }
+            }
+            grouped.push(digit);
+        }
+        let sign = if value.is_sign_negative() && !value.is_zero() {
+            "-"
+        } else {
+            ""
+        };
+        format!("{}{}{}{}", sign, grouped, self.decimal, cents)
+    }
+
+    // An amount with the currency, if there is one, as in -$12.50 or 12,50 €.
+    pub fn format_money(&self, value: Decimal) -> String {
+        let amount = self.format_amount(value);
+        match &self.currency {
+            None => amount,
+            Some(currency) if self.currency_after => format!("{} {}", amount, currency),
+            Some(currency) => {
+                let (sign, amount) = match amount.strip_prefix('-') {
+                    Some(amount) => ("-", amount),
+                    None => ("", amount.as_str()),
+                };
+                // Codes such as CHF are spaced from the amount, but symbols are not.
+                let space = if currency.chars().count() > 1 {
+                    " "
+                } else {
+                    ""
+                };
+                format!("{}{}{}{}", sign, currency, space, amount)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::locale::Locale;
+    use rust_decimal::prelude::*;
+
+    #[test]
+    fn format_in_locales() {
+        let english = Locale::default();
+        assert_eq!(english.format_amount(dec![1234567.5]), "1,234,567.50");
+        assert_eq!(english.format_amount(dec![300]), "300.00");
+        assert_eq!(english.format_amount(dec![-0.004]), "0.00");
+
+        let mut german: Locale = "de-DE".parse().unwrap();
+        assert_eq!(german.format_amount(dec![-1234.5]), "-1.234,50");
+        german.currency = Some("€".into());
+        assert_eq!(german.format_money(dec![12.5]), "12,50 €");
+
+        let mut swiss: Locale = "ch".parse().unwrap();
+        swiss.currency = Some("CHF".into());
+        assert_eq!(swiss.format_money(dec![-1234]), "-CHF 1'234.00");
+        let dollars = Locale {
+            currency: Some("$".into()),
+            ..Locale::default()
+        };
+        assert_eq!(dollars.format_money(dec![-12.5]), "-$12.50");
+
+        assert!("xx".parse::<Locale>().is_err());
+    }
+
+    #[test]
+    fn delimiters_cannot_be_part_of_amounts() {
+        let mut german: Locale = "de".parse().unwrap();
+        assert_eq!(german.delimiter, ';');
+        assert!(german.set_delimiter(",").is_err());
+        assert!(german.set_delimiter(".").is_err());
+        assert!(german.set_delimiter("ab").is_err());
+        german.set_delimiter("|").unwrap();
+        assert_eq!(german.delimiter, '|');
+        Locale::default().set_delimiter(",").unwrap();
+    }
+}
@@ -1 +1,11 @@
+pub mod alias;
+pub mod error;
+pub mod exact;
+pub mod explain;
+pub mod expression;
+pub mod history;
+pub mod locale;
 pub mod receipt;
+pub mod settle;
+pub mod summary;
+pub mod validation;
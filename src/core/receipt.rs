@@ -1,13 +1,16 @@
+pub use crate::core::error::{ErrorContext, InputSpan, SplittingError};
+use crate::core::locale::Locale;
 use crate::utils;
 use rust_decimal::prelude::*;
 use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::error::Error;
 use std::fmt;
 
-type Person = String;
-const LEFTOVER_ITEM_NAME: &'static str = "<leftover>";
-const TOTAL_ITEM_NAME: &'static str = "<total>";
+pub type Person = String;
+pub(crate) const LEFTOVER_ITEM_NAME: &str = "<leftover>";
+pub(crate) const TOTAL_ITEM_NAME: &str = "<total>";
+const PAID_ROW_NAME: &str = "<paid>";
+const NET_ROW_NAME: &str = "<net>";
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Receipt {
@@ -15,6 +18,38 @@ pub struct Receipt {
     pub shared_by: Vec<Person>,
     pub mapped_abbreviations: HashMap<Person, String>,
     pub items: Vec<ReceiptItem>,
+    pub payments: Vec<Payment>,
+    pub arithmetic: Arithmetic,
+    pub matching: MatchMode,
+    pub locale: Locale,
+}
+
+// How item values are divided among the people sharing them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Arithmetic {
+    // Decimal division, with each split rounded to cents on its own.
+    #[default]
+    Decimal,
+    // Exact fractions, converted to cents only at the end so that every row adds up
+    // to the item's value, and the totals to the receipt's value.
+    Exact,
+}
+
+// How abbreviations of the people sharing the receipt are matched to them. Whatever the
+// mode, a person's full name (or an alias) always means them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MatchMode {
+    // The abbreviation's characters appear in the name in the same order, in the same
+    // case. Don and don can be different people.
+    #[default]
+    Exact,
+    // As Exact, but ignoring case.
+    CaseInsensitive,
+    // The name starts with the abbreviation, ignoring case.
+    Prefix,
+    // As CaseInsensitive, or else the names closest to the abbreviation within a typo
+    // or two.
+    Fuzzy,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -28,39 +63,65 @@ pub struct ReceiptItem {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum SplittingError {
-    DuplicatePeopleError(String),
-    NotEnoughPeopleError(String),
-    InvalidShareConfiguration(String),
-    InvalidFieldError(String),
-    InvalidAbbreviation(String),
-    InternalError(String),
-    ItemTotalExceedsReceiptTotal(String),
-    DecimalParsingError(String),
-    InvalidArgument(String),
+pub enum PaymentMethod {
+    Cash,
+    Card,
+    Transfer,
+    Other(String),
+}
+
+// A part (or all) of the receipt's value settled by one of the people sharing it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Payment {
+    pub payer: Person,
+    pub amount: Decimal,
+    pub method: PaymentMethod,
 }
 
-impl From<rust_decimal::Error> for SplittingError {
-    fn from(e: rust_decimal::Error) -> SplittingError {
-        SplittingError::DecimalParsingError(e.to_string())
+impl FromStr for PaymentMethod {
+    type Err = SplittingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "cash" => Ok(Self::Cash),
+            "card" => Ok(Self::Card),
+            "transfer" => Ok(Self::Transfer),
+            "" => Err(SplittingError::InvalidFieldError(
+                ErrorContext::new("Payment method cannot be empty").with_value(s),
+            )),
+            _ => Ok(Self::Other(s.to_string())),
+        }
     }
 }
 
-// Required for main and Box<dyn std::error::Error>> returns to not complain
-impl Error for SplittingError {}
+impl FromStr for MatchMode {
+    type Err = SplittingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "exact" => Ok(Self::Exact),
+            "case-insensitive" | "ignore-case" => Ok(Self::CaseInsensitive),
+            "prefix" => Ok(Self::Prefix),
+            "fuzzy" => Ok(Self::Fuzzy),
+            _ => Err(SplittingError::InvalidArgument(
+                ErrorContext::new(format!(
+                    "{} is not a matching mode. Use one of exact, case-insensitive, prefix or fuzzy.",
+                    s
+                ))
+                .with_value(s)
+                .with_span(0, s.len()),
+            )),
+        }
+    }
+}
 
-impl fmt::Display for SplittingError {
+impl fmt::Display for PaymentMethod {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::DuplicatePeopleError(msg) => write!(f, "{}", msg),
-            Self::NotEnoughPeopleError(msg) => write!(f, "{}", msg),
-            Self::InvalidShareConfiguration(msg) => write!(f, "{}", msg),
-            Self::InvalidFieldError(msg) => write!(f, "{}", msg),
-            Self::InvalidAbbreviation(msg) => write!(f, "{}", msg),
-            Self::InternalError(msg) => write!(f, "{}", msg),
-            Self::ItemTotalExceedsReceiptTotal(msg) => write!(f, "{}", msg),
-            Self::DecimalParsingError(msg) => write!(f, "{}", msg),
-            Self::InvalidArgument(msg) => write!(f, "{}", msg),
+            Self::Cash => write!(f, "cash"),
+            Self::Card => write!(f, "card"),
+            Self::Transfer => write!(f, "transfer"),
+            Self::Other(method) => write!(f, "{}", method),
         }
     }
 }
@@ -70,16 +131,20 @@ impl Receipt {
     // Mapping is defaulted to a new, empty HashMap.
     // Items is an empty vector.
     pub fn new(value: Decimal, shared_by: Vec<&str>) -> Result<Receipt, SplittingError> {
-        utils::is_string_vec_unique(
-            &shared_by,
-            SplittingError::DuplicatePeopleError(
-                "The list of people sharing the receipt is duplicated. Please disambiguate.".into(),
-            ),
-        )?;
+        if let Some(duplicate) = utils::first_duplicate(&shared_by) {
+            return Err(SplittingError::DuplicatePeopleError(
+                ErrorContext::new(
+                    "The list of people sharing the receipt is duplicated. Please disambiguate.",
+                )
+                .with_person(duplicate)
+                .with_value(shared_by.join(",")),
+            ));
+        }
         utils::is_vec_len_gt_1(
             &shared_by,
             SplittingError::NotEnoughPeopleError(
-                "A receipt has to be shared by at least 2 people.".into(),
+                ErrorContext::new("A receipt has to be shared by at least 2 people.")
+                    .with_value(shared_by.join(",")),
             ),
         )?;
 
@@ -88,6 +153,10 @@ impl Receipt {
             shared_by: shared_by.iter().map(|&x| x.to_string()).collect(),
             mapped_abbreviations: HashMap::new(),
             items: vec![],
+            payments: vec![],
+            arithmetic: Arithmetic::default(),
+            matching: MatchMode::default(),
+            locale: Locale::default(),
         })
     }
 
@@ -99,24 +168,31 @@ impl Receipt {
         share_ratio: Option<Vec<Decimal>>,
     ) -> Result<&mut Self, SplittingError> {
         let share_ratio = share_ratio.unwrap_or(vec![Decimal::ONE; shared_by.len()]);
+        let item_idx = self.items.len();
 
         if shared_by.len() != share_ratio.len() {
-            return Err(SplittingError::InvalidShareConfiguration(format!(
-                "Length mismatch: people sharing {} and the ratios of the shares {} have differing lengths.",
-                shared_by.len(),
-                share_ratio.len()
-            )));
-        } else if shared_by.len() == 0 {
-            return Err(SplittingError::NotEnoughPeopleError(format!(
-                "The number of people sharing the item {} is {}. It must be shared by at least 1 person.",
-                name,
-                shared_by.len()
-            )));
+            return Err(SplittingError::InvalidShareConfiguration(
+                ErrorContext::new(format!(
+                    "Length mismatch: people sharing {} and the ratios of the shares {} have differing lengths.",
+                    shared_by.len(),
+                    share_ratio.len()
+                ))
+                .with_item(item_idx, &name),
+            ));
+        } else if shared_by.is_empty() {
+            return Err(SplittingError::NotEnoughPeopleError(
+                ErrorContext::new(format!(
+                    "The number of people sharing the item {} is {}. It must be shared by at least 1 person.",
+                    name,
+                    shared_by.len()
+                ))
+                .with_item(item_idx, &name),
+            ));
         }
 
         if name.is_empty() {
             return Err(SplittingError::InvalidFieldError(
-                "Item name cannot be empty".into(),
+                ErrorContext::new("Item name cannot be empty").with_item(item_idx, &name),
             ));
         }
 
@@ -132,7 +208,10 @@ impl Receipt {
 
     // Obtain a single vector with the exact splits, with or without items with
     // the is_prop_dist attribute as true
-    fn calculate_overall_proportion(&self, remove_proportional_items: bool) -> Vec<Decimal> {
+    pub(crate) fn calculate_overall_proportion(
+        &self,
+        remove_proportional_items: bool,
+    ) -> Vec<Decimal> {
         let items = self
             .items
             .iter()
@@ -154,7 +233,7 @@ impl Receipt {
                         // The first match is all that is required because other operations guarantee
                         // that duplicate names do not exist in either self.shared_by or item.shared_by
                         .find(|&(sharer, _)| *person == *sharer)
-                        .map(|(_, &numerator)| (numerator / denominator * item.value))
+                        .map(|(_, &numerator)| numerator / denominator * item.value)
                         .unwrap_or_else(|| Decimal::ZERO)
                 })
                 .collect();
@@ -172,21 +251,40 @@ impl Receipt {
         name: String,
         shared_by: Vec<String>,
     ) -> Result<&mut Self, SplittingError> {
-        if shared_by.len() == 0 {
-            return Err(SplittingError::NotEnoughPeopleError(format!(
-                "The number of people sharing the item {} is currently {}. It must be shared by at least 1 person.",
-                name,
-                shared_by.len()
-            )));
+        let item_idx = self.items.len();
+        if shared_by.is_empty() {
+            return Err(SplittingError::NotEnoughPeopleError(
+                ErrorContext::new(format!(
+                    "The number of people sharing the item {} is currently {}. It must be shared by at least 1 person.",
+                    name,
+                    shared_by.len()
+                ))
+                .with_item(item_idx, &name),
+            ));
         } else if name.is_empty() {
             return Err(SplittingError::InvalidFieldError(
-                "Item name cannot be empty".into(),
+                ErrorContext::new("Item name cannot be empty").with_item(item_idx, &name),
             ));
         }
 
         // These vectors are aligned with the order of self.shared_by
         let pre_prop_splits = self.calculate_overall_proportion(true);
         let pre_prop_split_total: Decimal = pre_prop_splits.iter().sum();
+        // The item is split in proportion to the items before it, so there must be some.
+        let no_base_error = |name: &str| {
+            SplittingError::InvalidShareConfiguration(
+                ErrorContext::new(format!(
+                    "{} is split in proportion to the items before it, but none of its people \
+                    share an item of any value.",
+                    name
+                ))
+                .with_item(item_idx, name)
+                .with_help("add the items it is in proportion to first"),
+            )
+        };
+        if pre_prop_split_total.is_zero() {
+            return Err(no_base_error(&name));
+        }
         let pre_prop_ratios: Vec<Decimal> = pre_prop_splits
             .iter()
             .map(|x| x / pre_prop_split_total)
@@ -202,13 +300,20 @@ impl Receipt {
                     .find(|&(_, person)| *sharer == *person)
                     .map(|(&ratio, _)| ratio * value)
                     .ok_or_else(|| {
-                        SplittingError::InternalError(format!(
-                            "The sharer {} was not found among the original sharers.",
-                            sharer,
-                        ))
+                        SplittingError::InternalError(
+                            ErrorContext::new(format!(
+                                "The sharer {} was not found among the original sharers.",
+                                sharer,
+                            ))
+                            .with_item(item_idx, &name)
+                            .with_person(sharer),
+                        )
                     })
             })
             .collect::<Result<_, _>>()?;
+        if share_ratio.iter().sum::<Decimal>().is_zero() {
+            return Err(no_base_error(&name));
+        }
 
         self.items.push(ReceiptItem {
             value,
@@ -226,6 +331,123 @@ impl Receipt {
         (itemized_total, leftover_amount)
     }
 
+    pub fn add_payment(
+        &mut self,
+        payer: String,
+        amount: Decimal,
+        method: PaymentMethod,
+    ) -> Result<&mut Self, SplittingError> {
+        if !self.shared_by.contains(&payer) {
+            return Err(SplittingError::InvalidFieldError(
+                ErrorContext::new(format!(
+                    "The payer {} is not one of the people sharing the receipt.",
+                    payer
+                ))
+                .with_person(&payer),
+            ));
+        } else if amount <= Decimal::ZERO {
+            return Err(SplittingError::InvalidFieldError(
+                ErrorContext::new(format!(
+                    "The amount paid by {} must be positive, but it is {}.",
+                    payer, amount
+                ))
+                .with_person(&payer)
+                .with_value(amount),
+            ));
+        }
+
+        let (paid_total, unpaid_amount) = self.get_paid_total_and_unpaid();
+        if amount > unpaid_amount {
+            return Err(SplittingError::PaymentTotalMismatch(
+                ErrorContext::new(format!(
+                    "The payment of {} by {} takes the paid total {} past the receipt's total amount {}",
+                    amount, payer, paid_total, self.value
+                ))
+                .with_person(&payer)
+                .with_value(amount),
+            ));
+        }
+
+        self.payments.push(Payment {
+            payer,
+            amount,
+            method,
+        });
+        Ok(self)
+    }
+
+    pub fn get_paid_total_and_unpaid(&self) -> (Decimal, Decimal) {
+        let paid_total: Decimal = self.payments.iter().map(|x| x.amount).sum();
+        let unpaid_amount: Decimal = self.value - paid_total;
+        (paid_total, unpaid_amount)
+    }
+
+    // Net position of each person, in the order of self.shared_by: what they paid minus
+    // what they owe. Positive values are owed to that person, negative values are owed
+    // by them. Only meaningful once the payments add up to the receipt's value.
+    pub fn calculate_net_positions(&self) -> Result<Vec<Decimal>, SplittingError> {
+        let (paid_total, unpaid_amount) = self.get_paid_total_and_unpaid();
+        if !unpaid_amount.is_zero() {
+            return Err(SplittingError::PaymentTotalMismatch(
+                ErrorContext::new(format!(
+                    "The payments total {} does not match the receipt's total amount {}",
+                    paid_total, self.value
+                ))
+                .with_value(paid_total),
+            ));
+        }
+
+        let (_, splits) = self.calculate_splits()?;
+        // The last row is always the total owed by each person.
+        let owed = splits.last().ok_or_else(|| {
+            SplittingError::InternalError("The calculated splits have no total row.".into())
+        })?;
+
+        Ok(self
+            .shared_by
+            .iter()
+            .zip(owed.iter())
+            .map(|(person, owed)| {
+                let paid: Decimal = self
+                    .payments
+                    .iter()
+                    .filter(|payment| payment.payer == *person)
+                    .map(|payment| payment.amount)
+                    .sum();
+                (paid - owed).round_dp(2)
+            })
+            .collect())
+    }
+
+    // Rows to display after the ones from calculate_splits: how much each person paid,
+    // and their net position. Empty if no payments have been recorded.
+    pub fn calculate_payment_splits(
+        &self,
+    ) -> Result<(Vec<&str>, Vec<Vec<Decimal>>), SplittingError> {
+        if self.payments.is_empty() {
+            return Ok((vec![], vec![]));
+        }
+
+        let net_positions = self.calculate_net_positions()?;
+        let mut paid: Vec<Decimal> = self
+            .shared_by
+            .iter()
+            .map(|person| {
+                self.payments
+                    .iter()
+                    .filter(|payment| payment.payer == *person)
+                    .map(|payment| payment.amount)
+                    .sum()
+            })
+            .collect();
+        paid.push(self.value);
+
+        let mut net = net_positions.clone();
+        net.push(net_positions.iter().sum());
+
+        Ok((vec![PAID_ROW_NAME, NET_ROW_NAME], vec![paid, net]))
+    }
+
     // Get a vector of item names (including leftovers and totals), as well as the splits
     // by each item so that they can be eventually displayed in a table easily, or used
     // for any other purpose.
@@ -237,27 +459,42 @@ impl Receipt {
             // There is a problem only if the leftover amount is negative
             Ordering::Greater | Ordering::Equal => {}
             Ordering::Less => {
-                return Err(SplittingError::ItemTotalExceedsReceiptTotal(format!(
-                    "The itemized total amount {} exceeds the receipt's total amount {} by {}",
-                    itemized_total, self.value, leftover_amount
-                )));
+                return Err(SplittingError::ItemTotalExceedsReceiptTotal(
+                    ErrorContext::new(format!(
+                        "The itemized total amount {} exceeds the receipt's total amount {} by {}",
+                        itemized_total, self.value, -leftover_amount
+                    ))
+                    .with_value(itemized_total),
+                ));
             }
         };
 
+        if self.arithmetic == Arithmetic::Exact {
+            return self.calculate_exact_splits(leftover_amount);
+        }
+
         let mut all_splits: Vec<Vec<Decimal>> = Vec::new();
 
         // Refactor needed - Receipts are short lived, so there is no point in
         // converting between ReceiptItem.shared_by and Receipt.shared_by - just
         // store shared_by in the same order as the receipt and display to the
         // user all the shared_by values that don't have 0 share ratio.
-        for item in self.items.iter() {
+        for (item_idx, item) in self.items.iter().enumerate() {
+            let ratio_sum: Decimal = item.share_ratio.iter().sum();
+            if ratio_sum.is_zero() {
+                return Err(SplittingError::InvalidShareConfiguration(
+                    ErrorContext::new(format!(
+                        "The share ratios of the item {} add up to zero.",
+                        item.name
+                    ))
+                    .with_item(item_idx, &item.name),
+                ));
+            }
             let mut splits: Vec<Decimal> = self
                 .shared_by
                 .iter()
                 .map(|x| match item.shared_by.iter().position(|name| name == x) {
-                    Some(pos) => (item.value * item.share_ratio[pos]
-                        / item.share_ratio.iter().sum::<Decimal>())
-                    .round_dp(2),
+                    Some(pos) => (item.value * item.share_ratio[pos] / ratio_sum).round_dp(2),
                     None => Decimal::ZERO.round_dp(2),
                 })
                 .collect();
@@ -271,6 +508,17 @@ impl Receipt {
         if leftover_amount > Decimal::ZERO {
             let overall_prop = self.calculate_overall_proportion(true);
             let overall_prop_sum: Decimal = overall_prop.iter().sum();
+            if overall_prop_sum.is_zero() {
+                return Err(SplittingError::InvalidShareConfiguration(
+                    ErrorContext::new(format!(
+                        "The leftover amount {} cannot be split, as there are no items to base \
+                        it on.",
+                        leftover_amount
+                    ))
+                    .with_value(leftover_amount)
+                    .with_help("add the items of the receipt, or make its total their sum"),
+                ));
+            }
             let mut splits: Vec<Decimal> = overall_prop
                 .iter()
                 .map(|x| (x * leftover_amount / overall_prop_sum).round_dp(2))
@@ -293,7 +541,7 @@ impl Receipt {
 
 #[cfg(test)]
 mod tests {
-    use crate::core::receipt::Receipt;
+    use crate::core::receipt::{PaymentMethod, Receipt, SplittingError};
     use crate::utils;
     use rust_decimal::prelude::*;
 
@@ -332,4 +580,84 @@ mod tests {
         ];
         assert_eq!(expected_splits, actual_splits);
     }
+
+    #[test]
+    fn test_leftover_without_items_is_an_error() {
+        let receipt = Receipt::new(dec![300], vec!["Alice", "Bob"]).unwrap();
+        assert!(matches!(
+            receipt.calculate_splits(),
+            Err(SplittingError::InvalidShareConfiguration(_))
+        ));
+
+        let mut receipt = Receipt::new(dec![300], vec!["Alice", "Bob"]).unwrap();
+        receipt
+            .add_item_split_by_ratio(
+                Decimal::ZERO,
+                "Food".into(),
+                utils::strs_to_strings(vec!["Alice", "Bob"]),
+                None,
+            )
+            .unwrap();
+        assert!(matches!(
+            receipt.calculate_splits(),
+            Err(SplittingError::InvalidShareConfiguration(_))
+        ));
+    }
+
+    #[test]
+    fn test_net_positions_with_split_payment() {
+        let mut receipt = Receipt::new(dec![300], vec!["Alice", "Bob", "Marshall"]).unwrap();
+        receipt
+            .add_item_split_by_ratio(
+                dec![200],
+                "Food".into(),
+                utils::strs_to_strings(vec!["Alice", "Bob", "Marshall"]),
+                None,
+            )
+            .unwrap();
+        receipt
+            .add_item_split_by_ratio(
+                dec![50],
+                "Drinks".into(),
+                utils::strs_to_strings(vec!["Alice", "Bob"]),
+                None,
+            )
+            .unwrap();
+        receipt
+            .add_payment("Alice".into(), dec![250], PaymentMethod::Card)
+            .unwrap();
+
+        // Net positions can only be calculated once the receipt is fully paid.
+        assert!(matches!(
+            receipt.calculate_net_positions(),
+            Err(SplittingError::PaymentTotalMismatch(_))
+        ));
+
+        receipt
+            .add_payment("Bob".into(), dec![50], PaymentMethod::Cash)
+            .unwrap();
+        assert_eq!(
+            receipt.calculate_net_positions().unwrap(),
+            vec![dec![140], dec![-60], dec![-80]]
+        );
+
+        let (row_names, rows) = receipt.calculate_payment_splits().unwrap();
+        assert_eq!(row_names, vec!["<paid>", "<net>"]);
+        assert_eq!(rows[0], vec![dec![250], dec![50], dec![0], dec![300]]);
+        assert_eq!(rows[1], vec![dec![140], dec![-60], dec![-80], dec![0]]);
+    }
+
+    #[test]
+    fn test_invalid_payments() {
+        let mut receipt = Receipt::new(dec![100], vec!["Alice", "Bob"]).unwrap();
+        assert!(matches!(
+            receipt.add_payment("Carol".into(), dec![10], PaymentMethod::Cash),
+            Err(SplittingError::InvalidFieldError(_))
+        ));
+        assert!(matches!(
+            receipt.add_payment("Alice".into(), dec![120], PaymentMethod::Cash),
+            Err(SplittingError::PaymentTotalMismatch(_))
+        ));
+        assert!(receipt.payments.is_empty());
+    }
 }
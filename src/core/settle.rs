@@ -0,0 +1,87 @@
+use crate::core::receipt::{Person, Receipt, SplittingError};
+use rust_decimal::prelude::*;
+
+// A payment from one person to another, to settle what they owe for a receipt.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transfer {
+    pub from: Person,
+    pub to: Person,
+    pub amount: Decimal,
+}
+
+impl Receipt {
+    // The transfers that settle everyone's net position once the payments add up to the
+    // receipt's value. The person owing the most pays the person owed the most until
+    // one of them is settled, which takes at most one transfer fewer than the number of
+    // people. A cent left over from rounding is not worth a transfer.
+    pub fn settle(&self) -> Result<Vec<Transfer>, SplittingError> {
+        let mut net = self.calculate_net_positions()?;
+        let mut transfers: Vec<Transfer> = Vec::new();
+        let cent = Decimal::new(1, 2);
+        loop {
+            let debtor = (0..net.len()).min_by_key(|&idx| net[idx]);
+            let creditor = (0..net.len()).max_by_key(|&idx| net[idx]);
+            let (debtor, creditor) = match (debtor, creditor) {
+                (Some(debtor), Some(creditor)) if -net[debtor] > cent && net[creditor] > cent => {
+                    (debtor, creditor)
+                }
+                _ => return Ok(transfers),
+            };
+            let amount = (-net[debtor]).min(net[creditor]);
+            net[debtor] += amount;
+            net[creditor] -= amount;
+            transfers.push(Transfer {
+                from: self.shared_by[debtor].clone(),
+                to: self.shared_by[creditor].clone(),
+                amount,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::receipt::{PaymentMethod, Receipt, SplittingError};
+    use crate::core::settle::Transfer;
+    use crate::utils;
+    use rust_decimal::prelude::*;
+
+    #[test]
+    fn settle_with_the_fewest_transfers() {
+        let mut receipt = Receipt::new(dec![300], vec!["Alice", "Bob", "Marshall"]).unwrap();
+        receipt
+            .add_item_split_by_ratio(
+                dec![300],
+                "Food".into(),
+                utils::strs_to_strings(vec!["Alice", "Bob", "Marshall"]),
+                None,
+            )
+            .unwrap();
+        assert!(matches!(
+            receipt.settle(),
+            Err(SplittingError::PaymentTotalMismatch(_))
+        ));
+
+        receipt
+            .add_payment("Alice".into(), dec![250], PaymentMethod::Card)
+            .unwrap();
+        receipt
+            .add_payment("Bob".into(), dec![50], PaymentMethod::Cash)
+            .unwrap();
+        assert_eq!(
+            receipt.settle().unwrap(),
+            vec![
+                Transfer {
+                    from: "Marshall".into(),
+                    to: "Alice".into(),
+                    amount: dec![100],
+                },
+                Transfer {
+                    from: "Bob".into(),
+                    to: "Alice".into(),
+                    amount: dec![50],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use crate::core::locale::Locale;
+use crate::core::receipt::{ErrorContext, Receipt, SplittingError, LEFTOVER_ITEM_NAME};
+use rust_decimal::prelude::*;
+
+// How a summary of the splits is written for a group chat.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SummaryStyle {
+    #[default]
+    Plain,
+    // Each line starts with an emoji, which most chat apps show well.
+    Emoji,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SummaryOptions {
+    pub style: SummaryStyle,
+    // Whether each person's line lists what they owe for each item.
+    pub itemized: bool,
+}
+
+impl FromStr for SummaryStyle {
+    type Err = SplittingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "plain" | "text" => Ok(Self::Plain),
+            "emoji" => Ok(Self::Emoji),
+            _ => Err(SplittingError::InvalidArgument(
+                ErrorContext::new(format!(
+                    "{} is not a summary style. Use one of plain or emoji.",
+                    s
+                ))
+                .with_value(s)
+                .with_span(0, s.len()),
+            )),
+        }
+    }
+}
+
+// Names as they would be written in a sentence, as in 'Alice, Bob and Marshall'.
+fn join_names(names: &[String]) -> String {
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+        _ => names.join(""),
+    }
+}
+
+impl Receipt {
+    // A message saying what everyone owes, and who pays whom once payments have been
+    // recorded, ready to be sent to the people sharing the receipt.
+    pub fn summarize(
+        &self,
+        options: SummaryOptions,
+        locale: &Locale,
+    ) -> Result<String, SplittingError> {
+        let emoji = options.style == SummaryStyle::Emoji;
+        let bullet = |symbol: &str| {
+            if emoji {
+                format!("{} ", symbol)
+            } else {
+                "- ".into()
+            }
+        };
+        let (item_names, splits) = self.calculate_splits()?;
+        // The last row is always the total owed by each person.
+        let (owed, item_splits) = splits.split_last().ok_or_else(|| {
+            SplittingError::InternalError("The calculated splits have no total row.".into())
+        })?;
+
+        let mut lines: Vec<String> = Vec::new();
+        lines.push(format!(
+            "{}{} split between {}:",
+            if emoji { "🧾 " } else { "" },
+            locale.format_money(self.value),
+            join_names(&self.shared_by)
+        ));
+        for (idx, person) in self.shared_by.iter().enumerate() {
+            let mut line = format!(
+                "{}{} owes {}",
+                bullet("👤"),
+                person,
+                locale.format_money(owed[idx])
+            );
+            if options.itemized {
+                let items: Vec<String> = item_names
+                    .iter()
+                    .zip(item_splits.iter())
+                    .filter(|(_, row)| !row[idx].is_zero())
+                    .map(|(&name, row)| {
+                        let name = if name == LEFTOVER_ITEM_NAME {
+                            "the rest"
+                        } else {
+                            name
+                        };
+                        format!("{} {}", name, locale.format_money(row[idx]))
+                    })
+                    .collect();
+                if !items.is_empty() {
+                    line.push_str(&format!(" ({})", items.join(", ")));
+                }
+            }
+            lines.push(line);
+        }
+
+        if !self.payments.is_empty() {
+            let transfers = self.settle()?;
+            if transfers.is_empty() {
+                lines.push(format!(
+                    "{}Everyone is settled up.",
+                    if emoji { "✅ " } else { "" }
+                ));
+            } else {
+                lines.push(format!("{}To settle up:", if emoji { "💸 " } else { "" }));
+            }
+            for transfer in transfers {
+                lines.push(format!(
+                    "{}{} pays {} {}",
+                    bullet("➡️"),
+                    transfer.from,
+                    transfer.to,
+                    locale.format_money(transfer.amount)
+                ));
+            }
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::locale::Locale;
+    use crate::core::receipt::{PaymentMethod, Receipt};
+    use crate::core::summary::{SummaryOptions, SummaryStyle};
+    use crate::utils;
+    use rust_decimal::prelude::*;
+
+    #[test]
+    fn summarize_the_splits() {
+        let mut receipt = Receipt::new(dec![300], vec!["Alice", "Bob", "Marshall"]).unwrap();
+        receipt
+            .add_item_split_by_ratio(
+                dec![200],
+                "Food".into(),
+                utils::strs_to_strings(vec!["Alice", "Bob", "Marshall"]),
+                None,
+            )
+            .unwrap();
+        receipt
+            .add_item_split_by_ratio(
+                dec![50],
+                "Drinks".into(),
+                utils::strs_to_strings(vec!["Alice", "Bob"]),
+                None,
+            )
+            .unwrap();
+        let locale = Locale::default();
+        assert_eq!(
+            receipt
+                .summarize(SummaryOptions::default(), &locale)
+                .unwrap(),
+            "\
+300.00 split between Alice, Bob and Marshall:
+- Alice owes 110.00
+- Bob owes 110.00
+- Marshall owes 80.00"
+        );
+
+        receipt
+            .add_payment("Alice".into(), dec![300], PaymentMethod::Card)
+            .unwrap();
+        let mut locale: Locale = "de".parse().unwrap();
+        locale.currency = Some("€".into());
+        let options = SummaryOptions {
+            style: SummaryStyle::Emoji,
+            itemized: true,
+        };
+        assert_eq!(
+            receipt.summarize(options, &locale).unwrap(),
+            "\
+🧾 300,00 € split between Alice, Bob and Marshall:
+👤 Alice owes 110,00 € (Food 66,67 €, Drinks 25,00 €, the rest 18,33 €)
+👤 Bob owes 110,00 € (Food 66,67 €, Drinks 25,00 €, the rest 18,33 €)
+👤 Marshall owes 80,00 € (Food 66,67 €, the rest 13,33 €)
+💸 To settle up:
+➡️ Bob pays Alice 110,00 €
+➡️ Marshall pays Alice 80,00 €"
+        );
+    }
+}
@@ -0,0 +1,368 @@
+use crate::core::receipt::{ErrorContext, Person, Receipt, SplittingError};
+use rust_decimal::prelude::*;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    // The receipt can still be split, but the result is probably not what was intended.
+    Warning,
+    // The receipt cannot be split until this is fixed.
+    Error,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IssueKind {
+    ZeroValueItem,
+    EmptyItemName,
+    ItemNotShared,
+    InvalidShareRatio,
+    OrphanSharer,
+    ProportionalItemWithoutBase,
+    LeftoverWithoutBase,
+    UnassignedPerson,
+    ItemTotalExceedsReceiptTotal,
+    PaymentTotalMismatch,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationIssue {
+    pub kind: IssueKind,
+    pub severity: Severity,
+    // Index into Receipt.items, if the issue is about a single item.
+    pub item_idx: Option<usize>,
+    pub person: Option<Person>,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    fn new(kind: IssueKind, severity: Severity, message: String) -> Self {
+        ValidationIssue {
+            kind,
+            severity,
+            item_idx: None,
+            person: None,
+            message,
+        }
+    }
+
+    fn for_item(mut self, item_idx: usize) -> Self {
+        self.item_idx = Some(item_idx);
+        self
+    }
+
+    fn for_person(mut self, person: &str) -> Self {
+        self.person = Some(person.to_string());
+        self
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        match self.item_idx {
+            Some(idx) => write!(f, "{} (item {}): {}", severity, idx + 1, self.message),
+            None => write!(f, "{}: {}", severity, self.message),
+        }
+    }
+}
+
+impl Receipt {
+    // List every problem with the receipt at once, unlike calculate_splits which stops
+    // at the first one. An empty list means the receipt can be split as it is.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues: Vec<ValidationIssue> = Vec::new();
+        let mut base_total = Decimal::ZERO;
+
+        for (idx, item) in self.items.iter().enumerate() {
+            let name = if item.name.is_empty() {
+                format!("Item {}", idx + 1)
+            } else {
+                item.name.clone()
+            };
+
+            if item.name.is_empty() {
+                issues.push(
+                    ValidationIssue::new(
+                        IssueKind::EmptyItemName,
+                        Severity::Error,
+                        "Item name cannot be empty".into(),
+                    )
+                    .for_item(idx),
+                );
+            }
+            // A negative item is a discount, such as a '-20% promo' line, and is taken off
+            // the shares of the people sharing it, so only a missing value is an issue.
+            if item.value.is_zero() {
+                issues.push(
+                    ValidationIssue::new(
+                        IssueKind::ZeroValueItem,
+                        Severity::Error,
+                        format!("{} does not have a value yet.", name),
+                    )
+                    .for_item(idx),
+                );
+            }
+            if item.shared_by.is_empty() {
+                issues.push(
+                    ValidationIssue::new(
+                        IssueKind::ItemNotShared,
+                        Severity::Error,
+                        format!("{} must be shared by at least 1 person.", name),
+                    )
+                    .for_item(idx),
+                );
+            }
+            if item.shared_by.len() != item.share_ratio.len()
+                || (!item.shared_by.is_empty()
+                    && item.share_ratio.iter().sum::<Decimal>().is_zero())
+            {
+                issues.push(
+                    ValidationIssue::new(
+                        IssueKind::InvalidShareRatio,
+                        Severity::Error,
+                        format!(
+                            "The share ratios of {} do not match the people sharing it.",
+                            name
+                        ),
+                    )
+                    .for_item(idx),
+                );
+            }
+            for sharer in item.shared_by.iter() {
+                if !self.shared_by.contains(sharer) {
+                    issues.push(
+                        ValidationIssue::new(
+                            IssueKind::OrphanSharer,
+                            Severity::Error,
+                            format!(
+                                "{} shares {}, but is not one of the people sharing the receipt.",
+                                sharer, name
+                            ),
+                        )
+                        .for_item(idx)
+                        .for_person(sharer),
+                    );
+                }
+            }
+
+            if item.is_prop_dist {
+                if base_total.is_zero() {
+                    issues.push(
+                        ValidationIssue::new(
+                            IssueKind::ProportionalItemWithoutBase,
+                            Severity::Error,
+                            format!(
+                                "{} is split proportionally, but there were no items before it \
+                                to base the proportions on.",
+                                name
+                            ),
+                        )
+                        .for_item(idx),
+                    );
+                }
+            } else {
+                base_total += item.value;
+            }
+        }
+
+        for person in self.shared_by.iter() {
+            if !self
+                .items
+                .iter()
+                .any(|item| !item.is_prop_dist && item.shared_by.contains(person))
+            {
+                issues.push(
+                    ValidationIssue::new(
+                        IssueKind::UnassignedPerson,
+                        Severity::Warning,
+                        format!("{} does not share any item, and will owe nothing.", person),
+                    )
+                    .for_person(person),
+                );
+            }
+        }
+
+        let (itemized_total, leftover_amount) = self.get_itemized_total_and_leftover();
+        if leftover_amount < Decimal::ZERO {
+            issues.push(ValidationIssue::new(
+                IssueKind::ItemTotalExceedsReceiptTotal,
+                Severity::Error,
+                format!(
+                    "The itemized total amount {} exceeds the receipt's total amount {} by {}",
+                    itemized_total, self.value, -leftover_amount
+                ),
+            ));
+        } else if leftover_amount > Decimal::ZERO && base_total.is_zero() {
+            issues.push(ValidationIssue::new(
+                IssueKind::LeftoverWithoutBase,
+                Severity::Error,
+                format!(
+                    "The leftover amount {} cannot be split, as there are no items to base it on.",
+                    leftover_amount
+                ),
+            ));
+        }
+
+        let (paid_total, unpaid_amount) = self.get_paid_total_and_unpaid();
+        if !self.payments.is_empty() && !unpaid_amount.is_zero() {
+            issues.push(ValidationIssue::new(
+                IssueKind::PaymentTotalMismatch,
+                Severity::Warning,
+                format!(
+                    "The payments total {} does not match the receipt's total amount {}",
+                    paid_total, self.value
+                ),
+            ));
+        }
+
+        issues
+    }
+
+    // The first issue that stops the receipt from being split, as the error it would be,
+    // so that the command line exits with the code of its kind.
+    pub fn check_splittable(&self) -> Result<(), SplittingError> {
+        let Some(issue) = self
+            .validate()
+            .into_iter()
+            .find(|issue| issue.severity == Severity::Error)
+        else {
+            return Ok(());
+        };
+        let mut context = ErrorContext::new(issue.message);
+        if let Some(item_idx) = issue.item_idx {
+            context = context.with_item(item_idx, &self.items[item_idx].name);
+        }
+        if let Some(person) = &issue.person {
+            context = context.with_person(person);
+        }
+        Err(match issue.kind {
+            IssueKind::ZeroValueItem | IssueKind::EmptyItemName | IssueKind::OrphanSharer => {
+                SplittingError::InvalidFieldError(context)
+            }
+            IssueKind::ItemNotShared | IssueKind::UnassignedPerson => {
+                SplittingError::NotEnoughPeopleError(context)
+            }
+            IssueKind::InvalidShareRatio => SplittingError::InvalidShareConfiguration(context),
+            IssueKind::ProportionalItemWithoutBase | IssueKind::LeftoverWithoutBase => {
+                SplittingError::InvalidShareConfiguration(
+                    context.with_help("add the items of the receipt, or make its total their sum"),
+                )
+            }
+            IssueKind::ItemTotalExceedsReceiptTotal => {
+                SplittingError::ItemTotalExceedsReceiptTotal(context)
+            }
+            IssueKind::PaymentTotalMismatch => SplittingError::PaymentTotalMismatch(context),
+        })
+    }
+
+    pub fn has_validation_errors(&self) -> bool {
+        self.validate()
+            .iter()
+            .any(|issue| issue.severity == Severity::Error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::receipt::{Receipt, ReceiptItem};
+    use crate::core::validation::{IssueKind, Severity};
+    use crate::utils;
+    use rust_decimal::prelude::*;
+
+    #[test]
+    fn test_valid_receipt_has_no_issues() {
+        let mut receipt = Receipt::new(dec![300], vec!["Alice", "Bob"]).unwrap();
+        receipt
+            .add_item_split_by_ratio(
+                dec![200],
+                "Food".into(),
+                utils::strs_to_strings(vec!["Alice", "Bob"]),
+                None,
+            )
+            .unwrap();
+        assert!(receipt.validate().is_empty());
+        assert!(!receipt.has_validation_errors());
+    }
+
+    #[test]
+    fn test_discounts_are_not_issues() {
+        let mut receipt = Receipt::new(dec![180], vec!["Alice", "Bob"]).unwrap();
+        receipt
+            .add_item_split_by_ratio(
+                dec![200],
+                "Food".into(),
+                utils::strs_to_strings(vec!["Alice", "Bob"]),
+                None,
+            )
+            .unwrap();
+        receipt
+            .add_item_split_by_ratio(
+                dec![-20],
+                "Promo".into(),
+                utils::strs_to_strings(vec!["Alice"]),
+                None,
+            )
+            .unwrap();
+        assert!(receipt.validate().is_empty());
+        let (_, splits) = receipt.calculate_splits().unwrap();
+        assert_eq!(
+            splits.last().unwrap(),
+            &vec![dec![80], dec![100], dec![180]]
+        );
+    }
+
+    #[test]
+    fn test_all_issues_are_reported() {
+        let mut receipt = Receipt::new(dec![100], vec!["Alice", "Bob", "Marshall"]).unwrap();
+        receipt.items.push(ReceiptItem {
+            value: dec![10],
+            name: "Tip".into(),
+            shared_by: utils::strs_to_strings(vec!["Alice", "Bob"]),
+            share_ratio: vec![dec![1], dec![1]],
+            is_prop_dist: true,
+        });
+        receipt.items.push(ReceiptItem {
+            value: Decimal::ZERO,
+            name: "".into(),
+            shared_by: utils::strs_to_strings(vec!["Alice"]),
+            share_ratio: vec![dec![1]],
+            is_prop_dist: false,
+        });
+        receipt.items.push(ReceiptItem {
+            value: dec![120],
+            name: "Food".into(),
+            shared_by: utils::strs_to_strings(vec!["Alice", "Carol"]),
+            share_ratio: vec![dec![1], dec![1]],
+            is_prop_dist: false,
+        });
+
+        let issues: Vec<(IssueKind, Severity, Option<usize>)> = receipt
+            .validate()
+            .into_iter()
+            .map(|issue| (issue.kind, issue.severity, issue.item_idx))
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                (
+                    IssueKind::ProportionalItemWithoutBase,
+                    Severity::Error,
+                    Some(0)
+                ),
+                (IssueKind::EmptyItemName, Severity::Error, Some(1)),
+                (IssueKind::ZeroValueItem, Severity::Error, Some(1)),
+                (IssueKind::OrphanSharer, Severity::Error, Some(2)),
+                (IssueKind::UnassignedPerson, Severity::Warning, None),
+                (IssueKind::UnassignedPerson, Severity::Warning, None),
+                (
+                    IssueKind::ItemTotalExceedsReceiptTotal,
+                    Severity::Error,
+                    None
+                ),
+            ]
+        );
+    }
+}
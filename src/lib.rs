@@ -3,4 +3,4 @@ pub mod core;
 pub mod utils;
 
 #[cfg(not(target_arch = "wasm32"))]
-mod cli;
+pub mod cli;
@@ -1,5 +1,4 @@
 use borrow_checker::app::frontend::App;
-use dioxus::prelude::*;
 
 fn main() {
     dioxus::launch(App);
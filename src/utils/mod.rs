@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 pub fn is_string_vec_unique<E>(vec: &[&str], error: E) -> Result<bool, E> {
     let mut seen = HashSet::new();
@@ -9,6 +9,11 @@ pub fn is_string_vec_unique<E>(vec: &[&str], error: E) -> Result<bool, E> {
     }
 }
 
+pub fn first_duplicate<'a>(vec: &[&'a str]) -> Option<&'a str> {
+    let mut seen = HashSet::new();
+    vec.iter().find(|&&s| !seen.insert(s)).copied()
+}
+
 pub fn is_vec_len_gt_1<E>(vec: &[&str], error: E) -> Result<bool, E> {
     if vec.is_empty() || vec.len() == 1 {
         Err(error)
@@ -18,9 +23,76 @@ pub fn is_vec_len_gt_1<E>(vec: &[&str], error: E) -> Result<bool, E> {
 }
 
 pub fn is_abbrev_match_to_string(abbrev: &str, name: &str) -> bool {
-    // Check if at least one or more characters provided as an "abbreviation"
-    // are present in a string i.e. it is a valid abbreviation.
-    abbrev.chars().all(|c| name.chars().any(|nc| nc == c))
+    // Check if the characters provided as an "abbreviation" appear in the string in
+    // the same order i.e. it is a valid abbreviation. "Hh" and "Hn" abbreviate
+    // "Hannah", but "nH" and "Hnnn" do not.
+    let mut name_chars = name.chars();
+    abbrev.chars().all(|c| name_chars.any(|nc| nc == c))
+}
+
+// Number of single character insertions, deletions, substitutions or swaps of adjacent
+// characters needed to turn one string into the other (optimal string alignment).
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    distances[0] = (0..=b.len()).collect();
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+// Assign each of a set of inputs to a distinct one of its candidates, such that every
+// input is assigned (a perfect bipartite matching). Returns the candidate assigned to
+// each input, or None if there is no such assignment.
+pub fn perfect_matching(candidates: &[Vec<usize>]) -> Option<Vec<usize>> {
+    // Augmenting paths (Kuhn's algorithm), which is plenty for the number of people
+    // sharing a receipt.
+    fn augment(
+        input: usize,
+        candidates: &[Vec<usize>],
+        assigned_to: &mut HashMap<usize, usize>,
+        visited: &mut HashSet<usize>,
+    ) -> bool {
+        for &candidate in candidates[input].iter() {
+            if visited.insert(candidate) {
+                let is_free = match assigned_to.get(&candidate) {
+                    Some(&other) => augment(other, candidates, assigned_to, visited),
+                    None => true,
+                };
+                if is_free {
+                    assigned_to.insert(candidate, input);
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    let mut assigned_to: HashMap<usize, usize> = HashMap::new();
+    for input in 0..candidates.len() {
+        if !augment(input, candidates, &mut assigned_to, &mut HashSet::new()) {
+            return None;
+        }
+    }
+    let mut assignment = vec![0; candidates.len()];
+    for (candidate, input) in assigned_to {
+        assignment[input] = candidate;
+    }
+    Some(assignment)
 }
 
 pub fn strs_to_strings(values: Vec<&str>) -> Vec<String> {
@@ -29,12 +101,31 @@ pub fn strs_to_strings(values: Vec<&str>) -> Vec<String> {
 
 #[cfg(test)]
 mod tests {
-    use super::is_abbrev_match_to_string;
+    use super::{edit_distance, is_abbrev_match_to_string, perfect_matching};
 
     #[test]
     fn match_person_to_name() {
-        assert_eq!(is_abbrev_match_to_string("Hn", "Hannah"), true);
-        assert_eq!(is_abbrev_match_to_string("Hh", "Hannah"), true);
-        assert_eq!(is_abbrev_match_to_string("Hb", "Hannah"), false);
+        assert!(is_abbrev_match_to_string("Hn", "Hannah"));
+        assert!(is_abbrev_match_to_string("Hh", "Hannah"));
+        assert!(is_abbrev_match_to_string("Hnn", "Hannah"));
+        assert!(!is_abbrev_match_to_string("Hb", "Hannah"));
+        assert!(!is_abbrev_match_to_string("nH", "Hannah"));
+        assert!(!is_abbrev_match_to_string("Hnnn", "Hannah"));
+    }
+
+    #[test]
+    fn count_typos() {
+        assert_eq!(edit_distance("alcie", "alice"), 1);
+        assert_eq!(edit_distance("alise", "alice"), 1);
+        assert_eq!(edit_distance("ally", "alice"), 3);
+        assert_eq!(edit_distance("", "bob"), 3);
+    }
+
+    #[test]
+    fn match_inputs_to_distinct_candidates() {
+        // The first input must give way to the second.
+        assert_eq!(perfect_matching(&[vec![0, 1], vec![0]]), Some(vec![1, 0]));
+        assert_eq!(perfect_matching(&[vec![0], vec![0]]), None);
+        assert_eq!(perfect_matching(&[]), Some(vec![]));
     }
 }